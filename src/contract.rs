@@ -1,15 +1,21 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
 };
 use cw2::{get_contract_version, set_contract_version};
 
 use crate::{
     error::ContractError,
+    executor,
     msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
     querier::{
-        aggregrate, block_aggregrate, block_try_aggregate_optional, block_try_aggregrate,
-        try_aggregate, try_aggregate_optional,
+        aggregate_raw, aggregate_requests, aggregrate, all_balances, balances, block_aggregate_raw,
+        block_aggregate_requests, block_aggregrate, block_all_balances, block_balances,
+        block_contract_infos, block_try_aggregate_optional, block_try_aggregate_raw,
+        block_try_aggregate_requests, block_try_aggregrate, chain, check_block_window,
+        contract_infos, cw20_balances, cw721_batch, format_response, guarded_aggregate,
+        map_addresses, map_values, oracle_price, paginate, reduce, truncate_response,
+        try_aggregate, try_aggregate_optional, try_aggregate_raw, try_aggregate_requests,
     },
 };
 
@@ -30,12 +36,28 @@ pub fn instantiate(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
-    _msg: ExecuteMsg,
+    info: MessageInfo,
+    msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    Err(ContractError::ExecuteNotSupported)
+    match msg {
+        ExecuteMsg::Aggregate { msgs } => executor::aggregate(info, msgs),
+        ExecuteMsg::TryAggregate {
+            include_cause,
+            msgs,
+        } => executor::try_aggregate(deps, info, include_cause, msgs),
+        ExecuteMsg::TryAggregateOptional {
+            include_cause,
+            msgs,
+        } => executor::try_aggregate_optional(deps, info, include_cause, msgs),
+        ExecuteMsg::Assert { assertions } => executor::assert(deps.as_ref(), assertions),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    executor::reply(deps, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -48,41 +70,340 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::ContractVersion {} => to_binary(&get_contract_version(deps.storage)?),
-        QueryMsg::Aggregate { queries } => to_binary(&aggregrate(deps, queries)?),
+        QueryMsg::Aggregate {
+            queries,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = aggregrate(deps, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
         QueryMsg::TryAggregate {
             require_success,
             include_cause,
+            include_error,
             queries,
-        } => to_binary(&try_aggregate(
-            deps,
+            budget,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = try_aggregate(
+                deps,
+                require_success,
+                include_cause,
+                include_error,
+                queries,
+                budget,
+            )?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::TryAggregateOptional {
+            include_cause,
+            include_error,
+            queries,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = try_aggregate_optional(deps, include_cause, include_error, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::BlockAggregate {
+            queries,
+            response_format,
+            max_response_bytes,
+            min_height,
+            max_height,
+            min_time,
+        } => {
+            check_block_window(&env, min_height, max_height, min_time)?;
+            let mut result = block_aggregrate(deps, env, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::BlockTryAggregate {
             require_success,
             include_cause,
+            include_error,
             queries,
-        )?),
-        QueryMsg::TryAggregateOptional {
+            budget,
+            response_format,
+            max_response_bytes,
+            min_height,
+            max_height,
+            min_time,
+        } => {
+            check_block_window(&env, min_height, max_height, min_time)?;
+            let mut result = block_try_aggregrate(
+                deps,
+                env,
+                require_success,
+                include_cause,
+                include_error,
+                queries,
+                budget,
+            )?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::BlockTryAggregateOptional {
             include_cause,
+            include_error,
             queries,
-        } => to_binary(&try_aggregate_optional(deps, include_cause, queries)?),
-        QueryMsg::BlockAggregate { queries } => to_binary(&block_aggregrate(deps, env, queries)?),
-        QueryMsg::BlockTryAggregate {
+            response_format,
+            max_response_bytes,
+            min_height,
+            max_height,
+            min_time,
+        } => {
+            check_block_window(&env, min_height, max_height, min_time)?;
+            let mut result =
+                block_try_aggregate_optional(deps, env, include_cause, include_error, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::AggregateRequests {
+            queries,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = aggregate_requests(deps, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::TryAggregateRequests {
             require_success,
             include_cause,
+            include_error,
             queries,
-        } => to_binary(&block_try_aggregrate(
-            deps,
-            env,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = try_aggregate_requests(
+                deps,
+                require_success,
+                include_cause,
+                include_error,
+                queries,
+            )?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::BlockAggregateRequests {
+            queries,
+            response_format,
+            max_response_bytes,
+            min_height,
+            max_height,
+            min_time,
+        } => {
+            check_block_window(&env, min_height, max_height, min_time)?;
+            let mut result = block_aggregate_requests(deps, env, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::BlockTryAggregateRequests {
             require_success,
             include_cause,
+            include_error,
             queries,
-        )?),
-        QueryMsg::BlockTryAggregateOptional {
+            response_format,
+            max_response_bytes,
+            min_height,
+            max_height,
+            min_time,
+        } => {
+            check_block_window(&env, min_height, max_height, min_time)?;
+            let mut result = block_try_aggregate_requests(
+                deps,
+                env,
+                require_success,
+                include_cause,
+                include_error,
+                queries,
+            )?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::AggregateRaw {
+            queries,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = aggregate_raw(deps, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::TryAggregateRaw {
+            require_success,
             include_cause,
+            include_error,
+            queries,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result =
+                try_aggregate_raw(deps, require_success, include_cause, include_error, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::BlockAggregateRaw {
             queries,
-        } => to_binary(&block_try_aggregate_optional(
+            response_format,
+            max_response_bytes,
+            min_height,
+            max_height,
+            min_time,
+        } => {
+            check_block_window(&env, min_height, max_height, min_time)?;
+            let mut result = block_aggregate_raw(deps, env, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::BlockTryAggregateRaw {
+            require_success,
+            include_cause,
+            include_error,
+            queries,
+            response_format,
+            max_response_bytes,
+            min_height,
+            max_height,
+            min_time,
+        } => {
+            check_block_window(&env, min_height, max_height, min_time)?;
+            let mut result = block_try_aggregate_raw(
+                deps,
+                env,
+                require_success,
+                include_cause,
+                include_error,
+                queries,
+            )?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::ContractInfos {
+            require_success,
+            include_cause,
+            addresses,
+        } => to_binary(&contract_infos(
+            deps,
+            require_success,
+            include_cause,
+            addresses,
+        )?),
+        QueryMsg::BlockContractInfos {
+            require_success,
+            include_cause,
+            addresses,
+        } => to_binary(&block_contract_infos(
             deps,
             env,
+            require_success,
+            include_cause,
+            addresses,
+        )?),
+        QueryMsg::Balances { addresses, denoms } => to_binary(&balances(deps, addresses, denoms)?),
+        QueryMsg::AllBalances { addresses } => to_binary(&all_balances(deps, addresses)?),
+        QueryMsg::BlockBalances { addresses, denoms } => {
+            to_binary(&block_balances(deps, env, addresses, denoms)?)
+        }
+        QueryMsg::BlockAllBalances { addresses } => {
+            to_binary(&block_all_balances(deps, env, addresses)?)
+        }
+        QueryMsg::Cw20Balances {
+            require_success,
+            include_cause,
+            tokens,
+            holders,
+        } => to_binary(&cw20_balances(
+            deps,
+            require_success,
             include_cause,
+            tokens,
+            holders,
+        )?),
+        QueryMsg::Cw721Batch {
+            include_cause,
+            include_expired,
+            queries,
+        } => to_binary(&cw721_batch(deps, include_cause, include_expired, queries)?),
+        QueryMsg::Chain {
             queries,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = chain(deps, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::GuardedAggregate {
+            queries,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = guarded_aggregate(deps, queries)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::Paginate {
+            address,
+            data,
+            start_after,
+            items,
+            cursor,
+            max_pages,
+        } => to_binary(&paginate(
+            deps,
+            address,
+            data,
+            start_after,
+            items,
+            cursor,
+            max_pages,
         )?),
+        QueryMsg::MapAddresses {
+            require_success,
+            include_cause,
+            data,
+            addresses,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = map_addresses(deps, require_success, include_cause, data, addresses)?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::MapValues {
+            require_success,
+            include_cause,
+            address,
+            template,
+            values,
+            response_format,
+            max_response_bytes,
+        } => {
+            let mut result = map_values(
+                deps,
+                require_success,
+                include_cause,
+                address,
+                template,
+                values,
+            )?;
+            truncate_response(&mut result.return_data, max_response_bytes);
+            format_response(&result, response_format)
+        }
+        QueryMsg::Reduce {
+            queries,
+            path,
+            include_results,
+        } => to_binary(&reduce(deps, queries, path, include_results)?),
+        QueryMsg::OraclePrice { oracles, max_age } => {
+            to_binary(&oracle_price(deps, env, oracles, max_age)?)
+        }
     }
 }
@@ -0,0 +1,14 @@
+use cosmwasm_std::Uint128;
+use serde::{Deserialize, Serialize};
+
+/// Subset of the cw20 query interface used to build balance queries.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20QueryMsg {
+    Balance { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cw20BalanceResponse {
+    pub balance: Uint128,
+}
@@ -1,4 +1,10 @@
-use cosmwasm_std::{Binary, StdError};
+use cosmwasm_std::{to_vec, Binary, Coin, StdError, SystemError};
+
+use crate::{
+    json::{self, Value},
+    msg::{AggregateError, CallError, ErrorKind},
+};
+use serde::Serialize;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,29 +12,83 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("Contract execution is not supported")]
-    ExecuteNotSupported,
+    #[error("Insufficient funds at index {index}, {coin} not covered by the funds sent")]
+    InsufficientFunds { index: usize, coin: Coin },
+
+    #[error(
+        "Unsupported message at index {index}: {kind} would act on the multicall contract itself"
+    )]
+    UnsupportedMessage { index: usize, kind: String },
+
+    #[error("Another execute batch is already awaiting replies")]
+    BatchInProgress {},
+
+    #[error("Required message at index {index} failed: {cause}")]
+    RequiredMessageFailed { index: usize, cause: String },
+
+    #[error("Unexpected reply for sub message {id}")]
+    UnexpectedReply { id: u64 },
 }
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum QueryError {
     #[error("Querier system error: {0}")]
-    System(String),
+    System(SystemError),
 
     #[error("Querier contract error: {0}")]
     Contract(String),
+
+    #[error("Unresolved placeholder {0}: {1}")]
+    Placeholder(String, String),
+
+    #[error("Unresolved selection {0}: {1}")]
+    Select(String, String),
+
+    #[error("Assertion failed on {0}: {1}")]
+    Assertion(String, String),
 }
 
 pub type QueryResult = core::result::Result<Binary, QueryError>;
 
 impl QueryError {
-    pub fn std_at_index(self, i: usize) -> StdError {
-        StdError::generic_err(format!("Error at index {}, {}", i, self))
+    /// Error failing a whole batch, its message is an `AggregateError` serialized as JSON.
+    pub fn std_at_index(self, i: usize, address: Option<String>) -> StdError {
+        let CallError {
+            kind,
+            system_error,
+            address,
+            message,
+        } = self.call_error(address);
+        let err = AggregateError {
+            index: i as u64,
+            kind,
+            system_error,
+            address,
+            message,
+        };
+
+        json_err(&err)
     }
 
     pub fn std(self) -> StdError {
         StdError::generic_err(self)
     }
+
+    /// Structured form of the error for the query sent to `address`.
+    pub fn call_error(&self, address: Option<String>) -> CallError {
+        let (kind, system_error, message) = match self {
+            QueryError::System(err) => (ErrorKind::System, system_error_name(err), err.to_string()),
+            QueryError::Contract(err) => (ErrorKind::Contract, None, err.clone()),
+            err => (ErrorKind::Multicall, None, err.to_string()),
+        };
+
+        CallError {
+            kind,
+            system_error,
+            address,
+            message,
+        }
+    }
 }
 
 impl From<QueryError> for String {
@@ -42,3 +102,19 @@ impl From<QueryError> for StdError {
         source.std()
     }
 }
+
+/// Error whose message is `payload` serialized as JSON, so clients can parse it.
+pub fn json_err<T: Serialize>(payload: &T) -> StdError {
+    match to_vec(payload) {
+        Ok(payload) => StdError::generic_err(String::from_utf8_lossy(&payload)),
+        Err(err) => err,
+    }
+}
+
+/// Serialized name of the `SystemError` variant, e.g. `no_such_contract`.
+fn system_error_name(err: &SystemError) -> Option<String> {
+    match json::from_slice(&to_vec(err).ok()?).ok()? {
+        Value::Object(entries) => entries.into_iter().next().map(|(name, _)| name),
+        _ => None,
+    }
+}
@@ -0,0 +1,245 @@
+use cosmwasm_std::{
+    to_binary, to_vec, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, MessageInfo, Reply,
+    Response, StdResult, SubMsg, SubMsgResult, Uint128, WasmMsg,
+};
+use std::collections::BTreeMap;
+
+use crate::{
+    error::ContractError,
+    json::{self, Value},
+    msg::{Assertion, CallResult, MsgOptional},
+    querier,
+    state::{PendingBatch, PENDING_BATCH},
+};
+
+/// Funds a message spends from the multicall contract balance.
+fn process_message_funds(msg: &CosmosMsg) -> Vec<Coin> {
+    match msg {
+        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => amount.clone(),
+        CosmosMsg::Bank(BankMsg::Burn { amount }) => amount.clone(),
+        CosmosMsg::Wasm(WasmMsg::Execute { funds, .. }) => funds.clone(),
+        CosmosMsg::Wasm(WasmMsg::Instantiate { funds, .. }) => funds.clone(),
+        _ => vec![],
+    }
+}
+
+/// Rejects messages whose effect stays with the multicall contract rather than the sender,
+/// e.g. a delegation or a contract admin, which any later caller could then take over.
+fn check_messages(msgs: &[CosmosMsg]) -> Result<(), ContractError> {
+    for (i, msg) in msgs.iter().enumerate() {
+        if matches!(
+            msg,
+            CosmosMsg::Staking(_)
+                | CosmosMsg::Distribution(_)
+                | CosmosMsg::Wasm(WasmMsg::Migrate { .. })
+                | CosmosMsg::Wasm(WasmMsg::UpdateAdmin { .. })
+                | CosmosMsg::Wasm(WasmMsg::ClearAdmin { .. })
+        ) {
+            return Err(ContractError::UnsupportedMessage {
+                index: i,
+                kind: process_message_kind(msg)?,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Names a message after its JSON module and variant, e.g. `wasm/execute`.
+fn process_message_kind(msg: &CosmosMsg) -> StdResult<String> {
+    let mut kind = vec![];
+    let mut value = json::from_slice(&to_vec(msg)?)?;
+    while let Value::Object(mut entries) = value {
+        match entries.len() {
+            1 if kind.len() < 2 => {
+                let (key, inner) = entries.remove(0);
+                kind.push(key);
+                value = inner;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(kind.join("/"))
+}
+
+/// Deducts the funds of every message from the funds sent, so that messages can never spend
+/// the contract's own balance, and returns what is left.
+fn process_funds(sent: &[Coin], msgs: &[CosmosMsg]) -> Result<Vec<Coin>, ContractError> {
+    let mut remaining: BTreeMap<String, Uint128> = BTreeMap::new();
+    for coin in sent {
+        *remaining.entry(coin.denom.clone()).or_default() += coin.amount;
+    }
+
+    for (i, msg) in msgs.iter().enumerate() {
+        for coin in process_message_funds(msg) {
+            let balance = remaining.entry(coin.denom.clone()).or_default();
+            *balance = balance
+                .checked_sub(coin.amount)
+                .map_err(|_| ContractError::InsufficientFunds { index: i, coin })?;
+        }
+    }
+
+    Ok(merge_coins(
+        remaining
+            .into_iter()
+            .map(|(denom, amount)| Coin { denom, amount })
+            .collect(),
+    ))
+}
+
+fn merge_coins(coins: Vec<Coin>) -> Vec<Coin> {
+    let mut merged: BTreeMap<String, Uint128> = BTreeMap::new();
+    for coin in coins {
+        *merged.entry(coin.denom).or_default() += coin.amount;
+    }
+
+    merged
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(denom, amount)| Coin { denom, amount })
+        .collect()
+}
+
+fn add_refund(response: Response, sender: String, refund: Vec<Coin>) -> Response {
+    match refund.is_empty() {
+        true => response,
+        false => response.add_message(BankMsg::Send {
+            to_address: sender,
+            amount: refund,
+        }),
+    }
+}
+
+pub fn aggregate(info: MessageInfo, msgs: Vec<CosmosMsg>) -> Result<Response, ContractError> {
+    check_messages(&msgs)?;
+    let remaining = process_funds(&info.funds, &msgs)?;
+    let mut response = Response::new().add_attribute("method", "aggregate");
+
+    for (i, msg) in msgs.into_iter().enumerate() {
+        response = response
+            .add_attribute(format!("msg_{}", i), process_message_kind(&msg)?)
+            .add_message(msg);
+    }
+
+    Ok(add_refund(response, info.sender.to_string(), remaining))
+}
+
+pub fn assert(deps: Deps, assertions: Vec<Assertion>) -> Result<Response, ContractError> {
+    querier::assert(deps, &assertions)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "assert")
+        .add_attribute("assertions", assertions.len().to_string()))
+}
+
+pub fn try_aggregate(
+    deps: DepsMut,
+    info: MessageInfo,
+    include_cause: Option<bool>,
+    msgs: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    let msgs = msgs
+        .into_iter()
+        .map(|msg| MsgOptional {
+            require_success: false,
+            msg,
+        })
+        .collect();
+
+    process_batch(deps, info, "try_aggregate", include_cause, msgs)
+}
+
+pub fn try_aggregate_optional(
+    deps: DepsMut,
+    info: MessageInfo,
+    include_cause: Option<bool>,
+    msgs: Vec<MsgOptional>,
+) -> Result<Response, ContractError> {
+    process_batch(deps, info, "try_aggregate_optional", include_cause, msgs)
+}
+
+/// Dispatches every message as a sub message replying to `reply`, so a failing message is
+/// rolled back alone and reported in the `Vec<CallResult>` set as the response data.
+fn process_batch(
+    deps: DepsMut,
+    info: MessageInfo,
+    method: &str,
+    include_cause: Option<bool>,
+    msgs: Vec<MsgOptional>,
+) -> Result<Response, ContractError> {
+    if PENDING_BATCH.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::BatchInProgress {});
+    }
+
+    let (require_success, msgs): (Vec<bool>, Vec<CosmosMsg>) = msgs
+        .into_iter()
+        .map(|item| (item.require_success, item.msg))
+        .unzip();
+    check_messages(&msgs)?;
+    let refund = process_funds(&info.funds, &msgs)?;
+    let mut response = Response::new().add_attribute("method", method);
+    if msgs.is_empty() {
+        return Ok(add_refund(response, info.sender.to_string(), refund)
+            .set_data(to_binary(&Vec::<CallResult>::new())?));
+    }
+
+    PENDING_BATCH.save(
+        deps.storage,
+        &PendingBatch {
+            sender: info.sender,
+            include_cause: include_cause.unwrap_or(false),
+            require_success,
+            funds: msgs.iter().map(process_message_funds).collect(),
+            refund,
+            return_data: Vec::with_capacity(msgs.len()),
+        },
+    )?;
+    for (i, msg) in msgs.into_iter().enumerate() {
+        response = response
+            .add_attribute(format!("msg_{}", i), process_message_kind(&msg)?)
+            .add_submessage(SubMsg::reply_always(msg, i as u64));
+    }
+
+    Ok(response)
+}
+
+pub fn reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let mut batch = PENDING_BATCH.load(deps.storage)?;
+    let index = batch.return_data.len();
+    if msg.id != index as u64 {
+        return Err(ContractError::UnexpectedReply { id: msg.id });
+    }
+
+    if let (true, SubMsgResult::Err(cause)) = (batch.require_success[index], &msg.result) {
+        return Err(ContractError::RequiredMessageFailed {
+            index,
+            cause: cause.clone(),
+        });
+    }
+
+    let success = matches!(msg.result, SubMsgResult::Ok(_));
+    batch.return_data.push(match msg.result {
+        SubMsgResult::Ok(res) => CallResult::from_data(res.data.unwrap_or_default()),
+        SubMsgResult::Err(err) => match batch.include_cause {
+            true => CallResult::from_failure(to_binary(&err)?),
+            false => CallResult::from_failure(Binary::default()),
+        },
+    });
+    if !success {
+        let funds = batch.funds[index].clone();
+        batch.refund = merge_coins([batch.refund, funds].concat());
+    }
+
+    let response = Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute(format!("msg_{}_success", index), success.to_string());
+    if batch.return_data.len() < batch.funds.len() {
+        PENDING_BATCH.save(deps.storage, &batch)?;
+        return Ok(response);
+    }
+
+    PENDING_BATCH.remove(deps.storage);
+    Ok(add_refund(response, batch.sender.to_string(), batch.refund)
+        .set_data(to_binary(&batch.return_data)?))
+}
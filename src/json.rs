@@ -0,0 +1,406 @@
+use cosmwasm_std::{StdError, StdResult};
+
+/// Nesting limit of parsed documents, keeps recursion bounded inside the wasm stack.
+const MAX_DEPTH: usize = 64;
+
+/// Minimal JSON document model used to inspect and rewrite query payloads.
+///
+/// Numbers are kept as their original text so no floating point operation is ever performed,
+/// which would make the contract rejected by the chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+pub fn from_slice(data: &[u8]) -> StdResult<Value> {
+    let mut parser = Parser { data, pos: 0 };
+    let value = parser.parse_value(0)?;
+    parser.skip_whitespace();
+
+    match parser.pos == data.len() {
+        true => Ok(value),
+        false => Err(parser.error("trailing characters")),
+    }
+}
+
+impl Value {
+    /// Looks up a value by JSON pointer (RFC 6901), e.g. `/pairs/0/contract_addr`.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+
+        pointer
+            .split('/')
+            .skip(1)
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .try_fold(self, |value, token| match value {
+                Value::Object(entries) => entries
+                    .iter()
+                    .find(|(key, _)| *key == token)
+                    .map(|(_, value)| value),
+                Value::Array(items) => parse_index(&token).and_then(|i| items.get(i)),
+                _ => None,
+            })
+    }
+
+    /// Replaces the value at a JSON pointer, the last key is added to its object when missing.
+    /// Returns `None` when the parent does not exist.
+    pub fn set(&mut self, pointer: &str, value: Value) -> Option<()> {
+        let (parent, token) = pointer.rsplit_once('/')?;
+        let token = token.replace("~1", "/").replace("~0", "~");
+
+        match self.pointer_mut(parent)? {
+            Value::Object(entries) => match entries.iter_mut().find(|(key, _)| *key == token) {
+                Some((_, entry)) => *entry = value,
+                None => entries.push((token, value)),
+            },
+            Value::Array(items) => *items.get_mut(parse_index(&token)?)? = value,
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+
+        pointer
+            .split('/')
+            .skip(1)
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .try_fold(self, |value, token| match value {
+                Value::Object(entries) => entries
+                    .iter_mut()
+                    .find(|(key, _)| *key == token)
+                    .map(|(_, value)| value),
+                Value::Array(items) => parse_index(&token).and_then(|i| items.get_mut(i)),
+                _ => None,
+            })
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Replaces every string value `f` returns a substitute for, keys are left untouched.
+    pub fn substitute<E>(
+        &mut self,
+        f: &mut impl FnMut(&str) -> Result<Option<Value>, E>,
+    ) -> Result<(), E> {
+        match self {
+            Value::String(s) => {
+                if let Some(value) = f(s)? {
+                    *self = value;
+                }
+            }
+            Value::Array(items) => {
+                for item in items.iter_mut() {
+                    item.substitute(f)?;
+                }
+            }
+            Value::Object(entries) => {
+                for (_, value) in entries.iter_mut() {
+                    value.substitute(f)?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write(&mut buf);
+        buf
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        match self {
+            Value::Null => buf.extend_from_slice(b"null"),
+            Value::Bool(true) => buf.extend_from_slice(b"true"),
+            Value::Bool(false) => buf.extend_from_slice(b"false"),
+            Value::Number(n) => buf.extend_from_slice(n.as_bytes()),
+            Value::String(s) => write_str(buf, s),
+            Value::Array(items) => {
+                buf.push(b'[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        buf.push(b',');
+                    }
+                    item.write(buf);
+                }
+                buf.push(b']');
+            }
+            Value::Object(entries) => {
+                buf.push(b'{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        buf.push(b',');
+                    }
+                    write_str(buf, key);
+                    buf.push(b':');
+                    value.write(buf);
+                }
+                buf.push(b'}');
+            }
+        }
+    }
+}
+
+fn parse_index(token: &str) -> Option<usize> {
+    match token.len() > 1 && token.starts_with('0') {
+        true => None,
+        false => token.parse().ok(),
+    }
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    buf.push(b'"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.extend_from_slice(b"\\\""),
+            '\\' => buf.extend_from_slice(b"\\\\"),
+            '\n' => buf.extend_from_slice(b"\\n"),
+            '\r' => buf.extend_from_slice(b"\\r"),
+            '\t' => buf.extend_from_slice(b"\\t"),
+            c if (c as u32) < 0x20 => {
+                buf.extend_from_slice(format!("\\u{:04x}", c as u32).as_bytes())
+            }
+            c => {
+                let mut utf8 = [0u8; 4];
+                buf.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+            }
+        }
+    }
+    buf.push(b'"');
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &str) -> StdError {
+        StdError::parse_err("json", format!("{} at position {}", msg, self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &[u8]) -> StdResult<()> {
+        match self.data[self.pos..].starts_with(literal) {
+            true => {
+                self.pos += literal.len();
+                Ok(())
+            }
+            false => Err(self.error("unexpected token")),
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> StdResult<Value> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'n') => self.expect(b"null").map(|_| Value::Null),
+            Some(b't') => self.expect(b"true").map(|_| Value::Bool(true)),
+            Some(b'f') => self.expect(b"false").map(|_| Value::Bool(false)),
+            Some(b'"') => self.parse_string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => self.parse_number().map(Value::Number),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'{') => self.parse_object(depth),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> StdResult<Value> {
+        let mut items = vec![];
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+
+        loop {
+            items.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> StdResult<Value> {
+        let mut entries = vec![];
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected object key"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(b":")?;
+            entries.push((key, self.parse_value(depth + 1)?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(entries));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> StdResult<String> {
+        let start = self.pos;
+        let digits = |parser: &mut Parser| {
+            let from = parser.pos;
+            while matches!(parser.peek(), Some(b'0'..=b'9')) {
+                parser.pos += 1;
+            }
+            parser.pos - from
+        };
+
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                digits(self);
+            }
+            _ => return Err(self.error("invalid number")),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if digits(self) == 0 {
+                return Err(self.error("invalid number"));
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if digits(self) == 0 {
+                return Err(self.error("invalid number"));
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&self.data[start..self.pos]).into_owned())
+    }
+
+    fn parse_string(&mut self) -> StdResult<String> {
+        let mut buf: Vec<u8> = vec![];
+        self.pos += 1;
+
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    return String::from_utf8(buf).map_err(|_| self.error("invalid utf-8"));
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    self.pos += 1;
+                    let mut utf8 = [0u8; 4];
+                    buf.extend_from_slice(escaped.encode_utf8(&mut utf8).as_bytes());
+                }
+                Some(c) if c < 0x20 => return Err(self.error("control character in string")),
+                Some(c) => {
+                    self.pos += 1;
+                    buf.push(c);
+                }
+            }
+        }
+    }
+
+    /// Parses the `XXXX` of `\uXXXX`, including surrogate pairs, leaving `pos` on the last digit.
+    fn parse_unicode_escape(&mut self) -> StdResult<char> {
+        let high = self.parse_hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                self.pos += 1;
+                self.expect(b"\\u")?;
+                self.pos -= 1;
+                let low = self.parse_hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(self.error("invalid surrogate pair"));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            code => code,
+        };
+
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn parse_hex4(&mut self) -> StdResult<u32> {
+        let hex = self
+            .data
+            .get(self.pos + 1..self.pos + 5)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(hex)
+    }
+}
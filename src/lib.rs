@@ -1,7 +1,12 @@
 pub mod contract;
+mod cw20;
+pub mod cw721;
 mod error;
+mod executor;
+mod json;
 pub mod msg;
 pub mod querier;
+mod state;
 
 #[cfg(test)]
 mod test;
@@ -1,10 +1,23 @@
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Addr, Api, Binary, Coin, ContractResult, Empty, OwnedDeps,
-    Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
+    from_binary, from_slice, to_binary, Addr, Api, Binary, Coin, ContractInfoResponse,
+    ContractResult, Empty, OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError,
+    SystemResult, Uint128, WasmQuery,
 };
 use serde::{Deserialize, Serialize};
-use std::marker::PhantomData;
+use std::{cell::Cell, marker::PhantomData};
+
+use crate::{
+    cw20::{Cw20BalanceResponse, Cw20QueryMsg},
+    cw721::{Approval, Cw721QueryMsg, Expiration, OwnerOfResponse},
+};
+
+pub const MOCK_MISSING_CONTRACT: &str = "missing_contract";
+pub const MOCK_MISSING_KEY: &[u8] = b"missing_key";
+pub const MOCK_BURNED_TOKEN: &str = "burned";
+/// Contract answering cw20 `balance` and contract info queries with a non standard response.
+pub const MOCK_MALFORMED_CONTRACT: &str = "malformed_contract";
+pub const MOCK_ACCOUNTS: u64 = 7;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum MockQueryMsg {
@@ -15,6 +28,14 @@ pub enum MockQueryMsg {
     Struct,
     StructAmount(u64),
     StructStr(String),
+    Accounts {
+        start_after: Option<u64>,
+        limit: u64,
+    },
+    Price {
+        price: String,
+        last_updated: u64,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -30,14 +51,39 @@ pub struct AnotherStructResponse {
     pub another_result: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MockAccount {
+    pub id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MockAccountsResponse {
+    pub accounts: Vec<MockAccount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MockPriceResponse {
+    pub price: String,
+    pub last_updated: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MockNftInfoResponse {
+    pub token_uri: Option<String>,
+    pub extension: SomeStructResponse,
+}
+
 pub fn mock_dependencies(
     contract_balance: &[Coin],
 ) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
-    let contract_addr = MOCK_CONTRACT_ADDR.to_string();
-    let custom_querier: WasmMockQuerier = WasmMockQuerier::new(
-        MockQuerier::<Empty>::new(&[(&contract_addr, contract_balance)]),
-        MockApi::default(),
-    );
+    mock_dependencies_with_balances(&[(MOCK_CONTRACT_ADDR, contract_balance)])
+}
+
+pub fn mock_dependencies_with_balances(
+    balances: &[(&str, &[Coin])],
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let custom_querier: WasmMockQuerier =
+        WasmMockQuerier::new(MockQuerier::<Empty>::new(balances), MockApi::default());
 
     OwnedDeps {
         storage: MockStorage::default(),
@@ -49,10 +95,12 @@ pub fn mock_dependencies(
 
 pub struct WasmMockQuerier {
     base: MockQuerier<Empty>,
+    executed: Cell<u64>,
 }
 
 impl Querier for WasmMockQuerier {
     fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        self.executed.set(self.executed.get() + 1);
         // MockQuerier doesn't support Custom, so we ignore it completely here
         let request: QueryRequest<Empty> = match from_slice(bin_request) {
             Ok(v) => v,
@@ -70,11 +118,21 @@ impl Querier for WasmMockQuerier {
 impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if from_binary::<Cw20QueryMsg>(msg).is_ok() =>
+            {
+                self.handle_cw20_query(contract_addr, from_binary(msg).unwrap())
+            }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if from_binary::<Cw721QueryMsg>(msg).is_ok() =>
+            {
+                self.handle_cw721_query(contract_addr, from_binary(msg).unwrap())
+            }
             QueryRequest::Wasm(WasmQuery::Smart {
                 contract_addr: _,
                 msg,
             }) => match from_binary(msg).unwrap() {
-                MockQueryMsg::One {} => SystemResult::Ok(ContractResult::Ok(
+                MockQueryMsg::One => SystemResult::Ok(ContractResult::Ok(
                     Binary::from_base64(base64::encode(b"1").as_str()).unwrap(),
                 )),
                 MockQueryMsg::Str(i) => SystemResult::Ok(ContractResult::Ok(
@@ -107,14 +165,140 @@ impl WasmMockQuerier {
                     })
                     .unwrap(),
                 )),
+                MockQueryMsg::Accounts { start_after, limit } => {
+                    let start = start_after.map_or(0, |id| id + 1);
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_binary(&MockAccountsResponse {
+                            accounts: (start..MOCK_ACCOUNTS.max(start))
+                                .take(limit as usize)
+                                .map(|id| MockAccount { id })
+                                .collect(),
+                        })
+                        .unwrap(),
+                    ))
+                }
+                MockQueryMsg::Price {
+                    price,
+                    last_updated,
+                } => SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&MockPriceResponse {
+                        price,
+                        last_updated,
+                    })
+                    .unwrap(),
+                )),
             },
+            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
+                match (contract_addr.as_str(), key.as_slice()) {
+                    (MOCK_MISSING_CONTRACT, _) => SystemResult::Err(SystemError::NoSuchContract {
+                        addr: contract_addr.clone(),
+                    }),
+                    (_, MOCK_MISSING_KEY) => {
+                        SystemResult::Ok(ContractResult::Ok(Binary::default()))
+                    }
+                    (_, key) => SystemResult::Ok(ContractResult::Ok(Binary::from(
+                        [b"value:", key].concat(),
+                    ))),
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::ContractInfo { contract_addr }) => {
+                match contract_addr.as_str() {
+                    MOCK_MISSING_CONTRACT => SystemResult::Err(SystemError::NoSuchContract {
+                        addr: contract_addr.clone(),
+                    }),
+                    MOCK_MALFORMED_CONTRACT => {
+                        SystemResult::Ok(ContractResult::Ok(Binary::from(br#"{"code_id":"1"}"#)))
+                    }
+                    _ => {
+                        let mut info =
+                            ContractInfoResponse::new(contract_addr.len() as u64, "creator");
+                        info.admin = Some(format!("{}_admin", contract_addr));
+                        SystemResult::Ok(ContractResult::Ok(to_binary(&info).unwrap()))
+                    }
+                }
+            }
             _ => self.base.handle_query(request),
         }
     }
 }
 
+impl WasmMockQuerier {
+    /// Holders own `len(token) * 1000 + len(holder)` tokens, `MOCK_MISSING_CONTRACT` is not a cw20.
+    fn handle_cw20_query(&self, contract_addr: &str, msg: Cw20QueryMsg) -> QuerierResult {
+        match (contract_addr, msg) {
+            (MOCK_MISSING_CONTRACT, _) => SystemResult::Err(SystemError::NoSuchContract {
+                addr: contract_addr.to_string(),
+            }),
+            (MOCK_MALFORMED_CONTRACT, _) => {
+                SystemResult::Ok(ContractResult::Ok(Binary::from(br#"{"amount":"1"}"#)))
+            }
+            (_, Cw20QueryMsg::Balance { address }) => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&Cw20BalanceResponse {
+                    balance: Uint128::from((contract_addr.len() * 1000 + address.len()) as u64),
+                })
+                .unwrap(),
+            )),
+        }
+    }
+
+    /// Tokens are owned by `{token_id}_owner`, `MOCK_BURNED_TOKEN` does not exist.
+    fn handle_cw721_query(&self, contract_addr: &str, msg: Cw721QueryMsg) -> QuerierResult {
+        match (contract_addr, msg) {
+            (MOCK_MISSING_CONTRACT, _) => SystemResult::Err(SystemError::NoSuchContract {
+                addr: contract_addr.to_string(),
+            }),
+            (_, Cw721QueryMsg::OwnerOf { token_id, .. })
+            | (_, Cw721QueryMsg::NftInfo { token_id })
+                if token_id == MOCK_BURNED_TOKEN =>
+            {
+                SystemResult::Ok(ContractResult::Err(String::from(
+                    "cw721_base::state::TokenInfo not found",
+                )))
+            }
+            (
+                _,
+                Cw721QueryMsg::OwnerOf {
+                    token_id,
+                    include_expired,
+                },
+            ) => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&OwnerOfResponse {
+                    owner: format!("{}_owner", token_id),
+                    approvals: match include_expired.unwrap_or(false) {
+                        true => vec![Approval {
+                            spender: String::from("operator"),
+                            expires: Expiration::AtHeight(1),
+                        }],
+                        false => vec![],
+                    },
+                })
+                .unwrap(),
+            )),
+            (_, Cw721QueryMsg::NftInfo { token_id }) => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&MockNftInfoResponse {
+                    token_uri: Some(format!("ipfs://{}/{}", contract_addr, token_id)),
+                    extension: SomeStructResponse {
+                        address: Addr::unchecked(contract_addr),
+                        amount: Uint128::zero(),
+                        list: vec![],
+                    },
+                })
+                .unwrap(),
+            )),
+        }
+    }
+}
+
 impl WasmMockQuerier {
     pub fn new<A: Api>(base: MockQuerier<Empty>, _api: A) -> Self {
-        WasmMockQuerier { base }
+        WasmMockQuerier {
+            base,
+            executed: Cell::new(0),
+        }
+    }
+
+    /// Number of queries that reached the querier.
+    pub fn executed(&self) -> u64 {
+        self.executed.get()
     }
 }
@@ -1,50 +1,315 @@
-use cosmwasm_std::{Addr, Binary};
+use cosmwasm_std::{
+    Addr, Binary, Coin, ContractInfoResponse, CosmosMsg, Decimal256, Empty, Env, QueryRequest,
+    Timestamp, Uint128,
+};
 use serde::{Deserialize, Serialize};
 
+use crate::cw721::Approval;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct InstantiateMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct MigrateMsg {}
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
-pub enum ExecuteMsg {}
+pub enum ExecuteMsg {
+    /// Dispatches every message atomically from the multicall contract. Messages may only spend
+    /// the funds sent along, the remainder is refunded to the sender.
+    Aggregate { msgs: Vec<CosmosMsg> },
+    /// Dispatches every message separately, a failing message is rolled back on its own and the
+    /// response data holds a `Vec<CallResult>` with the outcome of each message.
+    TryAggregate {
+        include_cause: Option<bool>,
+        msgs: Vec<CosmosMsg>,
+    },
+    /// Same as `TryAggregate`, but a failing message with `require_success` aborts the batch.
+    TryAggregateOptional {
+        include_cause: Option<bool>,
+        msgs: Vec<MsgOptional>,
+    },
+    /// Fails when any assertion does not hold, a batch can dispatch it to the multicall contract
+    /// to guard the messages around it.
+    Assert { assertions: Vec<Assertion> },
+}
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     ContractVersion {},
+    /// `max_response_bytes` caps the total size of the returned `data`, results that do not fit
+    /// are emptied and flagged `truncated`. Same on every query returning `return_data` as
+    /// `CallResult`s.
     Aggregate {
         queries: Vec<Call>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
     },
+    /// `include_error` adds a structured `error` to failed results, next to the `include_cause`
+    /// string. Same on every try aggregate.
     TryAggregate {
         require_success: Option<bool>,
         include_cause: Option<bool>,
+        include_error: Option<bool>,
         queries: Vec<Call>,
+        budget: Option<Budget>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
     },
     TryAggregateOptional {
         include_cause: Option<bool>,
+        include_error: Option<bool>,
         queries: Vec<CallOptional>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
     },
+    /// `min_height`, `max_height` and `min_time` bound the block the queries run at, so a stale
+    /// node fails the query instead of returning an old snapshot. Same on every block aggregate.
     BlockAggregate {
         queries: Vec<Call>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+        min_height: Option<u64>,
+        max_height: Option<u64>,
+        min_time: Option<Timestamp>,
     },
     BlockTryAggregate {
         require_success: Option<bool>,
         include_cause: Option<bool>,
+        include_error: Option<bool>,
         queries: Vec<Call>,
+        budget: Option<Budget>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+        min_height: Option<u64>,
+        max_height: Option<u64>,
+        min_time: Option<Timestamp>,
     },
     BlockTryAggregateOptional {
         include_cause: Option<bool>,
+        include_error: Option<bool>,
         queries: Vec<CallOptional>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+        min_height: Option<u64>,
+        max_height: Option<u64>,
+        min_time: Option<Timestamp>,
+    },
+    AggregateRequests {
+        queries: Vec<QueryRequest<Empty>>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+    },
+    TryAggregateRequests {
+        require_success: Option<bool>,
+        include_cause: Option<bool>,
+        include_error: Option<bool>,
+        queries: Vec<QueryRequest<Empty>>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+    },
+    BlockAggregateRequests {
+        queries: Vec<QueryRequest<Empty>>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+        min_height: Option<u64>,
+        max_height: Option<u64>,
+        min_time: Option<Timestamp>,
+    },
+    BlockTryAggregateRequests {
+        require_success: Option<bool>,
+        include_cause: Option<bool>,
+        include_error: Option<bool>,
+        queries: Vec<QueryRequest<Empty>>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+        min_height: Option<u64>,
+        max_height: Option<u64>,
+        min_time: Option<Timestamp>,
+    },
+    AggregateRaw {
+        queries: Vec<RawCall>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+    },
+    TryAggregateRaw {
+        require_success: Option<bool>,
+        include_cause: Option<bool>,
+        include_error: Option<bool>,
+        queries: Vec<RawCall>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+    },
+    BlockAggregateRaw {
+        queries: Vec<RawCall>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+        min_height: Option<u64>,
+        max_height: Option<u64>,
+        min_time: Option<Timestamp>,
+    },
+    BlockTryAggregateRaw {
+        require_success: Option<bool>,
+        include_cause: Option<bool>,
+        include_error: Option<bool>,
+        queries: Vec<RawCall>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+        min_height: Option<u64>,
+        max_height: Option<u64>,
+        min_time: Option<Timestamp>,
+    },
+    ContractInfos {
+        require_success: Option<bool>,
+        include_cause: Option<bool>,
+        addresses: Vec<Addr>,
+    },
+    BlockContractInfos {
+        require_success: Option<bool>,
+        include_cause: Option<bool>,
+        addresses: Vec<Addr>,
+    },
+    Balances {
+        addresses: Vec<Addr>,
+        denoms: Vec<String>,
+    },
+    AllBalances {
+        addresses: Vec<Addr>,
+    },
+    BlockBalances {
+        addresses: Vec<Addr>,
+        denoms: Vec<String>,
+    },
+    BlockAllBalances {
+        addresses: Vec<Addr>,
+    },
+    Cw20Balances {
+        require_success: Option<bool>,
+        include_cause: Option<bool>,
+        tokens: Vec<Addr>,
+        holders: Vec<Addr>,
+    },
+    Cw721Batch {
+        include_cause: Option<bool>,
+        include_expired: Option<bool>,
+        queries: Vec<Cw721Call>,
+    },
+    /// Like `aggregate`, but `address` and string values inside `data` may be `$<index><pointer>`
+    /// placeholders, replaced by the JSON value at that pointer in a prior result.
+    Chain {
+        queries: Vec<Call>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+    },
+    /// Like `aggregate`, but assertions may be interleaved with the queries, the first failing
+    /// assertion aborts the batch. An assertion returns the value it compared.
+    GuardedAggregate {
+        queries: Vec<CallKind>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+    },
+    /// Queries `data` page after page, each time setting the JSON pointer `start_after` of the
+    /// message to the `cursor` of the last item of the array at `items`, until a page is empty
+    /// or `max_pages` pages were read, at least one.
+    Paginate {
+        address: Addr,
+        data: Binary,
+        start_after: String,
+        items: String,
+        /// JSON pointer of the cursor inside an item, the whole item when not set.
+        cursor: Option<String>,
+        max_pages: u64,
+    },
+    /// Sends the same `data` to every address, `return_data[i]` is the result of `addresses[i]`.
+    MapAddresses {
+        require_success: Option<bool>,
+        include_cause: Option<bool>,
+        data: Binary,
+        addresses: Vec<Addr>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+    },
+    /// Sends `template` once per value, with `$v` inside its string values replaced by the value
+    /// and `$$` by a literal `$`. `return_data[i]` is the result of `values[i]`.
+    MapValues {
+        require_success: Option<bool>,
+        include_cause: Option<bool>,
+        address: Addr,
+        template: Binary,
+        values: Vec<String>,
+        response_format: Option<ResponseFormat>,
+        max_response_bytes: Option<u64>,
+    },
+    /// Runs `queries` like `aggregate` and reduces the decimal at the JSON pointer `path` of
+    /// every result, e.g. a `Uint128` balance or a `Decimal` price.
+    Reduce {
+        queries: Vec<Call>,
+        path: String,
+        include_results: Option<bool>,
+    },
+    /// Median price of `oracles`, ignoring failed queries, prices older than `max_age` seconds
+    /// at the current block time and prices dated after it.
+    OraclePrice {
+        oracles: Vec<OracleSource>,
+        max_age: u64,
     },
 }
 
+/// Encoding of `data` in each returned `CallResult`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// `data` is the base64 encoded query response, the default.
+    Base64,
+    /// JSON responses are embedded as-is under `json` instead of `data`, other responses are
+    /// still returned base64 encoded as `data`.
+    Json,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Call {
     pub address: Addr,
     pub data: Binary,
+    /// JSON pointers to project the response onto, returned as an object keyed by pointer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub select: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CallKind {
+    Query(Call),
+    Assert(Assertion),
+}
+
+/// Compares the JSON value at `path` in the source result against `value`. Both sides are
+/// compared as decimals when they parse as one, otherwise only `eq` and `ne` are allowed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Assertion {
+    pub source: AssertSource,
+    pub path: String,
+    pub op: CompareOp,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssertSource {
+    /// Result of a prior query in the same batch.
+    Index(u64),
+    Query(Call),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -52,33 +317,396 @@ pub struct CallOptional {
     pub require_success: bool,
     pub address: Addr,
     pub data: Binary,
+    /// JSON pointers to project the response onto, returned as an object keyed by pointer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub select: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MsgOptional {
+    pub require_success: bool,
+    pub msg: CosmosMsg,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RawCall {
+    pub address: Addr,
+    pub key: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cw721Call {
+    pub require_success: bool,
+    pub collection: Addr,
+    pub token_id: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
 pub struct CallResult {
     pub success: bool,
     pub data: Binary,
+    /// Only set for raw storage reads, `false` when the key is absent from the contract storage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub found: Option<bool>,
+    /// Set when `data` was dropped to keep the response within `max_response_bytes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<bool>,
+    /// Why the query failed, only set when `include_error` is `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<CallError>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The chain could not run the query, e.g. the contract does not exist.
+    System,
+    /// The queried contract returned an error.
+    Contract,
+    /// The multicall contract could not process the query or its response.
+    Multicall,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CallError {
+    pub kind: ErrorKind,
+    /// `SystemError` variant, e.g. `no_such_contract`, only set for system errors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReduceResult {
+    pub count: u64,
+    pub sum: Decimal256,
+    pub min: Decimal256,
+    pub max: Decimal256,
+    pub mean: Decimal256,
+    pub median: Decimal256,
+    /// Results the values were read from, only set when `include_results` is `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_data: Option<Vec<CallResult>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OracleSource {
+    pub address: Addr,
+    pub data: Binary,
+    /// JSON pointer to the price in the oracle response.
+    pub price_path: String,
+    /// JSON pointer to the unix time in seconds the price was updated at.
+    pub time_path: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OraclePriceSource {
+    pub address: Addr,
+    pub price: Decimal256,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OraclePriceResult {
+    pub median: Decimal256,
+    /// Oracles the median was taken from, in the order they were given.
+    pub sources: Vec<OraclePriceSource>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PaginateResult {
+    /// JSON array of the items of every page read.
+    pub items: Binary,
+    pub pages: u64,
+    /// JSON encoded `start_after` to resume from, not set once the list is exhausted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_start_after: Option<Binary>,
+}
+
+/// Limits of a batch, queries past them are left out and reported by `next_index`. The first
+/// query is always executed so a resumed batch makes progress.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct Budget {
+    pub max_calls: Option<u64>,
+    /// Stops once the returned `data` adds up to this many bytes.
+    pub max_payload_bytes: Option<u64>,
+}
+
+/// Failure of a whole batch, serialized as JSON into the `StdError::GenericErr` message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AggregateError {
+    /// Index of the failing item in the batch.
+    pub index: u64,
+    pub kind: ErrorKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub message: String,
+}
+
+/// Block outside of the requested window, serialized as JSON into the `StdError::GenericErr`
+/// message so a client can tell a lagging node apart and retry against another one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockWindowError {
+    pub height: u64,
+    /// Block time in nanoseconds since the epoch.
+    pub time: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_height: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_time: Option<Timestamp>,
+    pub message: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct AggregateResult {
     pub return_data: Vec<CallResult>,
+    /// Number of queries actually executed, identical queries in a batch are executed once.
+    #[serde(default)]
+    pub executed: u64,
+    /// Index of the first query left out once the budget was spent, to resume from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_index: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct BlockAggregateResult {
     pub block: u64,
+    /// Block time in nanoseconds since the epoch.
+    pub time: Timestamp,
+    pub chain_id: String,
+    /// Address of the multicall contract that ran the queries.
+    pub contract: Addr,
     pub return_data: Vec<CallResult>,
+    /// Number of queries actually executed, identical queries in a batch are executed once.
+    #[serde(default)]
+    pub executed: u64,
+    /// Index of the first query left out once the budget was spent, to resume from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_index: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ContractInfoResult {
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<ContractInfoResponse>,
+    /// Error message of a failed query, only set when `include_cause` is `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ContractInfosResult {
+    pub return_data: Vec<ContractInfoResult>,
+    /// Number of queries actually executed, identical queries in a batch are executed once.
+    #[serde(default)]
+    pub executed: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BlockContractInfosResult {
+    pub block: u64,
+    /// Block time in nanoseconds since the epoch.
+    pub time: Timestamp,
+    pub chain_id: String,
+    /// Address of the multicall contract that ran the queries.
+    pub contract: Addr,
+    pub return_data: Vec<ContractInfoResult>,
+    /// Number of queries actually executed, identical queries in a batch are executed once.
+    #[serde(default)]
+    pub executed: u64,
+}
+
+/// `balances[i]` holds the coins of `addresses[i]`, in the order of `denoms` for `balances`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BalancesResult {
+    pub balances: Vec<Vec<Coin>>,
+    /// Number of queries actually executed, identical queries in a batch are executed once.
+    #[serde(default)]
+    pub executed: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BlockBalancesResult {
+    pub block: u64,
+    /// Block time in nanoseconds since the epoch.
+    pub time: Timestamp,
+    pub chain_id: String,
+    /// Address of the multicall contract that ran the queries.
+    pub contract: Addr,
+    pub balances: Vec<Vec<Coin>>,
+    /// Number of queries actually executed, identical queries in a batch are executed once.
+    #[serde(default)]
+    pub executed: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cw20BalanceResult {
+    pub token: Addr,
+    pub holder: Addr,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<Uint128>,
+    /// Error message of a failed query, only set when `include_cause` is `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+}
+
+/// Results are ordered by token first, then by holder.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cw20BalancesResult {
+    pub return_data: Vec<Cw20BalanceResult>,
+    /// Number of queries actually executed, identical queries in a batch are executed once.
+    #[serde(default)]
+    pub executed: u64,
+}
+
+/// `success` is only `true` when both `owner_of` and `nft_info` succeeded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cw721Result {
+    pub collection: Addr,
+    pub token_id: String,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approvals: Option<Vec<Approval>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_uri: Option<String>,
+    /// Error message of a failed query, only set when `include_cause` is `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cw721BatchResult {
+    pub return_data: Vec<Cw721Result>,
+    /// Number of queries actually executed, identical queries in a batch are executed once.
+    #[serde(default)]
+    pub executed: u64,
 }
 
 impl AggregateResult {
-    pub fn from_return_data(return_data: Vec<CallResult>) -> AggregateResult {
-        AggregateResult { return_data }
+    pub fn from_return_data(return_data: Vec<CallResult>, executed: u64) -> AggregateResult {
+        AggregateResult {
+            return_data,
+            executed,
+            next_index: None,
+        }
+    }
+}
+
+impl CallResult {
+    pub fn from_data(data: Binary) -> CallResult {
+        CallResult {
+            success: true,
+            data,
+            ..CallResult::default()
+        }
+    }
+
+    pub fn from_failure(data: Binary) -> CallResult {
+        CallResult {
+            success: false,
+            data,
+            ..CallResult::default()
+        }
     }
 }
 
 impl BlockAggregateResult {
-    pub fn from_return_data(block: u64, return_data: Vec<CallResult>) -> BlockAggregateResult {
-        BlockAggregateResult { block, return_data }
+    pub fn from_return_data(
+        env: &Env,
+        return_data: Vec<CallResult>,
+        executed: u64,
+    ) -> BlockAggregateResult {
+        BlockAggregateResult {
+            block: env.block.height,
+            time: env.block.time,
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.clone(),
+            return_data,
+            executed,
+            next_index: None,
+        }
+    }
+}
+
+impl ContractInfosResult {
+    pub fn from_return_data(
+        return_data: Vec<ContractInfoResult>,
+        executed: u64,
+    ) -> ContractInfosResult {
+        ContractInfosResult {
+            return_data,
+            executed,
+        }
+    }
+}
+
+impl BlockContractInfosResult {
+    pub fn from_return_data(
+        env: &Env,
+        return_data: Vec<ContractInfoResult>,
+        executed: u64,
+    ) -> BlockContractInfosResult {
+        BlockContractInfosResult {
+            block: env.block.height,
+            time: env.block.time,
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.clone(),
+            return_data,
+            executed,
+        }
+    }
+}
+
+impl BalancesResult {
+    pub fn from_balances(balances: Vec<Vec<Coin>>, executed: u64) -> BalancesResult {
+        BalancesResult { balances, executed }
+    }
+}
+
+impl BlockBalancesResult {
+    pub fn from_balances(
+        env: &Env,
+        balances: Vec<Vec<Coin>>,
+        executed: u64,
+    ) -> BlockBalancesResult {
+        BlockBalancesResult {
+            block: env.block.height,
+            time: env.block.time,
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.clone(),
+            balances,
+            executed,
+        }
+    }
+}
+
+impl Cw20BalancesResult {
+    pub fn from_return_data(
+        return_data: Vec<Cw20BalanceResult>,
+        executed: u64,
+    ) -> Cw20BalancesResult {
+        Cw20BalancesResult {
+            return_data,
+            executed,
+        }
+    }
+}
+
+impl Cw721BatchResult {
+    pub fn from_return_data(return_data: Vec<Cw721Result>, executed: u64) -> Cw721BatchResult {
+        Cw721BatchResult {
+            return_data,
+            executed,
+        }
     }
 }
@@ -1,16 +1,85 @@
 use cosmwasm_std::{
-    to_binary, to_vec, Addr, Binary, ContractResult, Deps, Empty, Env, QuerierResult, QueryRequest,
-    StdResult, SystemResult, WasmQuery,
+    from_binary, to_binary, to_vec, Addr, AllBalanceResponse, BalanceResponse, BankQuery, Binary,
+    Coin, ContractResult, Decimal256, Deps, Empty, Env, QuerierResult, QueryRequest, StdError,
+    StdResult, SystemResult, Timestamp, Uint256, WasmQuery,
 };
 
+use serde::{de::DeserializeOwned, Serialize};
+use std::{cmp::Ordering, collections::BTreeMap, str::FromStr};
+
 use crate::{
-    error::{QueryError, QueryResult},
-    msg::{AggregateResult, BlockAggregateResult, Call, CallOptional, CallResult},
+    cw20::{Cw20BalanceResponse, Cw20QueryMsg},
+    cw721::{Cw721QueryMsg, NftInfoResponse, OwnerOfResponse},
+    error::{json_err, QueryError, QueryResult},
+    json::{self, Value},
+    msg::{
+        AggregateResult, AssertSource, Assertion, BalancesResult, BlockAggregateResult,
+        BlockBalancesResult, BlockContractInfosResult, BlockWindowError, Budget, Call, CallKind,
+        CallOptional, CallResult, CompareOp, ContractInfoResult, ContractInfosResult,
+        Cw20BalanceResult, Cw20BalancesResult, Cw721BatchResult, Cw721Call, Cw721Result,
+        OraclePriceResult, OraclePriceSource, OracleSource, PaginateResult, RawCall, ReduceResult,
+        ResponseFormat,
+    },
 };
 
+/// Outcome of a request already executed in the batch.
+enum Cached {
+    /// Success kept as is in `return_data` at this index.
+    At(usize),
+    Response(Binary),
+    Failed(QueryError),
+}
+
+/// Executes each distinct request of a batch once, repeated requests are served from memory.
+struct BatchQuerier<'a> {
+    deps: Deps<'a>,
+    cache: BTreeMap<Vec<u8>, Cached>,
+    executed: u64,
+}
+
+impl<'a> BatchQuerier<'a> {
+    fn new(deps: Deps<'a>) -> Self {
+        BatchQuerier {
+            deps,
+            cache: BTreeMap::new(),
+            executed: 0,
+        }
+    }
+
+    fn query(&mut self, request: Vec<u8>) -> QueryResult {
+        self.query_at(request, None, &[])
+    }
+
+    /// Same as `query` for a call whose response is returned as is at index `at` of
+    /// `return_data`, a repeated request then reads it back from there instead of a copy.
+    fn query_at(
+        &mut self,
+        request: Vec<u8>,
+        at: Option<usize>,
+        return_data: &[CallResult],
+    ) -> QueryResult {
+        match self.cache.get(&request) {
+            Some(Cached::At(i)) => return Ok(return_data[*i].data.clone()),
+            Some(Cached::Response(data)) => return Ok(data.clone()),
+            Some(Cached::Failed(err)) => return Err(err.clone()),
+            None => {}
+        }
+
+        let result = process_query_result(self.deps.querier.raw_query(&request));
+        self.executed += 1;
+        let cached = match (&result, at) {
+            (Err(err), _) => Cached::Failed(err.clone()),
+            (Ok(_), Some(i)) => Cached::At(i),
+            (Ok(data), None) => Cached::Response(data.clone()),
+        };
+        self.cache.insert(request, cached);
+        result
+    }
+}
+
 fn process_query_result(result: QuerierResult) -> QueryResult {
     match result {
-        SystemResult::Err(system_err) => Err(QueryError::System(system_err.to_string())),
+        SystemResult::Err(system_err) => Err(QueryError::System(system_err)),
         SystemResult::Ok(ContractResult::Err(contract_err)) => {
             Err(QueryError::Contract(contract_err))
         }
@@ -25,17 +94,460 @@ fn process_wasm_query(address: Addr, binary: Binary) -> StdResult<Vec<u8>> {
     }))
 }
 
+fn process_raw_query(query: RawCall) -> QueryRequest<Empty> {
+    QueryRequest::Wasm(WasmQuery::Raw {
+        contract_addr: query.address.to_string(),
+        key: query.key,
+    })
+}
+
+/// Missing keys come back as successful empty reads, flag them so they can be told apart.
+fn process_raw_result(mut result: AggregateResult) -> AggregateResult {
+    for call in result.return_data.iter_mut().filter(|call| call.success) {
+        call.found = Some(!call.data.is_empty());
+    }
+
+    result
+}
+
+/// Decodes a successful result into `T`, a response that does not decode fails like the query
+/// did, or the whole batch when `require_success` is set. Returns the success, the decoded value
+/// and the cause of a failure if it was included.
+fn process_typed_result<T: DeserializeOwned>(
+    result: &CallResult,
+    i: usize,
+    address: Option<String>,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+) -> StdResult<(bool, Option<T>, Option<String>)> {
+    if !result.success {
+        return Ok(match result.data.is_empty() {
+            true => (false, None, None),
+            false => (false, None, Some(from_binary(&result.data)?)),
+        });
+    }
+
+    match from_binary(&result.data) {
+        Ok(value) => Ok((true, Some(value), None)),
+        Err(err) => {
+            let err = QueryError::Contract(err.to_string());
+            match (
+                require_success.unwrap_or(false),
+                include_cause.unwrap_or(false),
+            ) {
+                (true, _) => Err(err.std_at_index(i, address)),
+                (false, true) => Ok((false, None, Some(err.to_string()))),
+                (false, false) => Ok((false, None, None)),
+            }
+        }
+    }
+}
+
+/// Resolves a `$<index><pointer>` placeholder, e.g. `$0/contract_addr`, against prior results.
+/// A leading `$$` escapes a literal `$`, e.g. `$$1INCH` is kept as `$1INCH`.
+fn process_placeholder(
+    result: &[CallResult],
+    placeholder: &str,
+) -> Result<Option<Value>, QueryError> {
+    if let Some(literal) = placeholder.strip_prefix("$$") {
+        return Ok(Some(Value::String(format!("${}", literal))));
+    }
+    let reference = match placeholder.strip_prefix('$') {
+        Some(reference) if reference.starts_with(|c: char| c.is_ascii_digit()) => reference,
+        _ => return Ok(None),
+    };
+    let (index, pointer) = reference.split_at(
+        reference
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(reference.len()),
+    );
+    let unresolved =
+        |cause: &str| QueryError::Placeholder(placeholder.to_string(), cause.to_string());
+
+    let data = index
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| result.get(index))
+        .ok_or_else(|| unresolved("no prior result at this index"))?;
+    let value = json::from_slice(&data.data).map_err(|_| unresolved("result is not JSON"))?;
+
+    value
+        .pointer(pointer)
+        .cloned()
+        .map(Some)
+        .ok_or_else(|| unresolved("path not found in result"))
+}
+
+fn process_chain_call(result: &[CallResult], query: Call) -> Result<Call, QueryError> {
+    let address = match process_placeholder(result, query.address.as_str())? {
+        Some(Value::String(address)) => Addr::unchecked(address),
+        Some(_) => {
+            return Err(QueryError::Placeholder(
+                query.address.to_string(),
+                String::from("address must resolve to a string"),
+            ))
+        }
+        None => query.address,
+    };
+    let data = match json::from_slice(&query.data) {
+        Ok(mut value) => {
+            value.substitute(&mut |s| process_placeholder(result, s))?;
+            Binary::from(value.to_vec())
+        }
+        Err(_) => query.data,
+    };
+
+    Ok(Call {
+        address,
+        data,
+        select: query.select,
+    })
+}
+
+/// Projects a response onto the selected JSON pointers, as an object keyed by pointer.
+fn process_select(data: Binary, select: &Option<Vec<String>>) -> QueryResult {
+    let select = match select {
+        Some(select) => select,
+        None => return Ok(data),
+    };
+    let value = json::from_slice(&data)
+        .map_err(|_| QueryError::Select(select.join(", "), String::from("response is not JSON")))?;
+
+    let entries = select
+        .iter()
+        .map(|pointer| match value.pointer(pointer) {
+            Some(selected) => Ok((pointer.clone(), selected.clone())),
+            None => Err(QueryError::Select(
+                pointer.clone(),
+                String::from("path not found in response"),
+            )),
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Binary::from(Value::Object(entries).to_vec()))
+}
+
+/// Compares a JSON value against a literal, as decimals when both sides parse as one.
+fn compare(actual: &Value, op: CompareOp, expected: &str) -> Result<bool, String> {
+    let actual = match actual {
+        Value::Number(n) | Value::String(n) => n.clone(),
+        value => String::from_utf8_lossy(&value.to_vec()).into_owned(),
+    };
+    let ordering = match (
+        Decimal256::from_str(&actual),
+        Decimal256::from_str(expected),
+    ) {
+        (Ok(actual), Ok(expected)) => actual.cmp(&expected),
+        _ => match op {
+            CompareOp::Eq => return Ok(actual == expected),
+            CompareOp::Ne => return Ok(actual != expected),
+            _ => return Err(format!("{} is not comparable to {}", actual, expected)),
+        },
+    };
+
+    Ok(match op {
+        CompareOp::Eq => ordering == Ordering::Equal,
+        CompareOp::Ne => ordering != Ordering::Equal,
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Lte => ordering != Ordering::Greater,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Gte => ordering != Ordering::Less,
+    })
+}
+
+fn process_assertion_address(assertion: &Assertion) -> Option<String> {
+    match &assertion.source {
+        AssertSource::Query(call) => Some(call.address.to_string()),
+        AssertSource::Index(_) => None,
+    }
+}
+
+/// Evaluates an assertion against a prior result or its own query, returning the compared value.
+fn process_assertion(
+    querier: &mut BatchQuerier,
+    result: &[CallResult],
+    assertion: &Assertion,
+) -> StdResult<QueryResult> {
+    let failed = |cause: String| QueryError::Assertion(assertion.path.clone(), cause);
+    let data = match &assertion.source {
+        AssertSource::Index(index) => result
+            .get(*index as usize)
+            .map(|call| call.data.clone())
+            .ok_or_else(|| failed(format!("no prior result at index {}", index))),
+        AssertSource::Query(call) => querier
+            .query(process_wasm_query(call.address.clone(), call.data.clone())?)
+            .and_then(|res| process_select(res, &call.select)),
+    };
+
+    Ok(data.and_then(|data| {
+        let value =
+            json::from_slice(&data).map_err(|_| failed(String::from("result is not JSON")))?;
+        let actual = value
+            .pointer(&assertion.path)
+            .ok_or_else(|| failed(String::from("path not found in result")))?;
+
+        match compare(actual, assertion.op, &assertion.value).map_err(failed)? {
+            true => Ok(Binary::from(actual.to_vec())),
+            false => Err(failed(format!(
+                "found {}, expected {:?} {}",
+                String::from_utf8_lossy(&actual.to_vec()),
+                assertion.op,
+                assertion.value
+            ))),
+        }
+    }))
+}
+
+/// Replaces every `$v` token of a template string by `value`, `None` when nothing changes. `$$`
+/// escapes a literal `$`, and `$v` followed by a letter or digit, e.g. `$vault`, is kept as is.
+fn process_template_string(s: &str, value: &str) -> Option<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    let mut changed = false;
+
+    while let Some(i) = rest.find('$') {
+        result.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if let Some(after) = tail.strip_prefix("$$") {
+            result.push('$');
+            rest = after;
+            changed = true;
+        } else if let Some(after) = tail
+            .strip_prefix("$v")
+            .filter(|after| !after.starts_with(|c: char| c.is_ascii_alphanumeric()))
+        {
+            result.push_str(value);
+            rest = after;
+            changed = true;
+        } else {
+            result.push('$');
+            rest = &tail[1..];
+        }
+    }
+    result.push_str(rest);
+
+    match changed {
+        true => Some(result),
+        false => None,
+    }
+}
+
+/// Reads the decimal at a JSON pointer, either a JSON number or a string like `Uint128`.
+fn process_decimal(data: &[u8], path: &str) -> Result<Decimal256, QueryError> {
+    let unresolved = |cause: &str| QueryError::Select(path.to_string(), cause.to_string());
+    let value = json::from_slice(data).map_err(|_| unresolved("response is not JSON"))?;
+
+    match value.pointer(path) {
+        Some(Value::Number(n) | Value::String(n)) => {
+            Decimal256::from_str(n).map_err(|_| unresolved("value is not a decimal"))
+        }
+        Some(_) => Err(unresolved("value is not a decimal")),
+        None => Err(unresolved("path not found in response")),
+    }
+}
+
+/// Reads the unix time in seconds at a JSON pointer, either a JSON number or a string.
+fn process_seconds(data: &[u8], path: &str) -> Result<u64, QueryError> {
+    let unresolved = |cause: &str| QueryError::Select(path.to_string(), cause.to_string());
+    let value = json::from_slice(data).map_err(|_| unresolved("response is not JSON"))?;
+
+    match value.pointer(path) {
+        Some(Value::Number(n) | Value::String(n)) => n
+            .parse::<u64>()
+            .map_err(|_| unresolved("value is not a timestamp")),
+        Some(_) => Err(unresolved("value is not a timestamp")),
+        None => Err(unresolved("path not found in response")),
+    }
+}
+
+/// Median of sorted values, the mean of the two middle values for an even count.
+fn median(sorted: &[Decimal256]) -> Decimal256 {
+    let n = sorted.len();
+    match n % 2 {
+        0 => {
+            // Halved separately so that values close to `Decimal256::MAX` cannot overflow.
+            let two = Uint256::from(2u8);
+            let (a, b) = (sorted[n / 2 - 1].atomics(), sorted[n / 2].atomics());
+            Decimal256::new(a / two + b / two + (a % two + b % two) / two)
+        }
+        _ => sorted[n / 2],
+    }
+}
+
+fn checked_sum(values: &[Decimal256]) -> StdResult<Decimal256> {
+    let sum = values.iter().try_fold(Uint256::zero(), |sum, value| {
+        sum.checked_add(value.atomics())
+    })?;
+
+    Ok(Decimal256::new(sum))
+}
+
+/// Splits a page into its items and the cursor of its last item, `None` once a page is empty.
+fn process_page(
+    data: &[u8],
+    items: &str,
+    cursor: &Option<String>,
+) -> Result<Option<(Vec<Value>, Value)>, QueryError> {
+    let unresolved =
+        |pointer: &str, cause: &str| QueryError::Select(pointer.to_string(), cause.to_string());
+    let page = json::from_slice(data).map_err(|_| unresolved(items, "response is not JSON"))?;
+    let page_items = match page.pointer(items) {
+        Some(Value::Array(page_items)) => page_items.clone(),
+        _ => return Err(unresolved(items, "items are not an array")),
+    };
+
+    let next = match (page_items.last(), cursor) {
+        (None, _) => return Ok(None),
+        (Some(last), None) => last.clone(),
+        (Some(last), Some(cursor)) => last
+            .pointer(cursor)
+            .cloned()
+            .ok_or_else(|| unresolved(cursor, "path not found in item"))?,
+    };
+
+    Ok(Some((page_items, next)))
+}
+
+/// Swaps the base64 `data` of a `CallResult` for its decoded `json` value when it is JSON.
+fn process_json_call_result(call: &mut Value) {
+    let entries = match call {
+        Value::Object(entries) => entries,
+        _ => return,
+    };
+
+    for (key, value) in entries.iter_mut().filter(|(key, _)| key == "data") {
+        let embedded = value
+            .as_str()
+            .and_then(|data| Binary::from_base64(data).ok())
+            .and_then(|data| json::from_slice(&data).ok());
+        if let Some(embedded) = embedded {
+            *key = String::from("json");
+            *value = embedded;
+        }
+    }
+}
+
+/// Whether a batch that ran `calls` queries returning `payload` bytes has spent its budget.
+fn budget_spent(budget: &Budget, calls: u64, payload: u64) -> bool {
+    budget.max_calls.is_some_and(|max| calls >= max)
+        || budget.max_payload_bytes.is_some_and(|max| payload >= max)
+}
+
+fn process_failure(
+    err: QueryError,
+    address: Option<String>,
+    include_cause: Option<bool>,
+    include_error: Option<bool>,
+) -> StdResult<CallResult> {
+    let mut result = match include_cause.unwrap_or(false) {
+        true => CallResult::from_failure(to_binary(&err.to_string())?),
+        false => CallResult::from_failure(Binary::default()),
+    };
+    if include_error.unwrap_or(false) {
+        result.error = Some(err.call_error(address));
+    }
+
+    Ok(result)
+}
+
+/// Address a request is sent to, if it targets a single account or contract.
+fn process_request_address(query: &QueryRequest<Empty>) -> Option<String> {
+    match query {
+        QueryRequest::Wasm(WasmQuery::Smart { contract_addr, .. })
+        | QueryRequest::Wasm(WasmQuery::Raw { contract_addr, .. })
+        | QueryRequest::Wasm(WasmQuery::ContractInfo { contract_addr }) => {
+            Some(contract_addr.clone())
+        }
+        QueryRequest::Bank(BankQuery::Balance { address, .. })
+        | QueryRequest::Bank(BankQuery::AllBalances { address }) => Some(address.clone()),
+        _ => None,
+    }
+}
+
+/// Empties the results that do not fit in `max_response_bytes` of `data` once the ones before
+/// them are returned, later smaller results may still fit.
+pub fn truncate_response(return_data: &mut [CallResult], max_response_bytes: Option<u64>) {
+    let max = match max_response_bytes {
+        Some(max) => max,
+        None => return,
+    };
+
+    let mut size = 0u64;
+    for call in return_data.iter_mut() {
+        let len = call.data.len() as u64;
+        match size + len > max {
+            true => {
+                call.data = Binary::default();
+                call.truncated = Some(true);
+            }
+            false => size += len,
+        }
+    }
+}
+
+pub fn format_response<T: Serialize>(
+    result: &T,
+    response_format: Option<ResponseFormat>,
+) -> StdResult<Binary> {
+    let response = to_binary(result)?;
+    if response_format.unwrap_or(ResponseFormat::Base64) == ResponseFormat::Base64 {
+        return Ok(response);
+    }
+
+    let mut response = json::from_slice(&response)?;
+    if let Value::Object(entries) = &mut response {
+        for (_, value) in entries.iter_mut().filter(|(key, _)| key == "return_data") {
+            if let Value::Array(calls) = value {
+                calls.iter_mut().for_each(process_json_call_result);
+            }
+        }
+    }
+
+    Ok(Binary::from(response.to_vec()))
+}
+
+/// Rejects a block outside of the requested window, typically one served by a lagging node.
+pub fn check_block_window(
+    env: &Env,
+    min_height: Option<u64>,
+    max_height: Option<u64>,
+    min_time: Option<Timestamp>,
+) -> StdResult<()> {
+    let block = &env.block;
+    let message = match (min_height, max_height, min_time) {
+        (Some(min), _, _) if block.height < min => {
+            format!("height {} is below min_height {}", block.height, min)
+        }
+        (_, Some(max), _) if block.height > max => {
+            format!("height {} is above max_height {}", block.height, max)
+        }
+        (_, _, Some(min)) if block.time < min => {
+            format!("time {} is before min_time {}", block.time, min)
+        }
+        _ => return Ok(()),
+    };
+
+    Err(json_err(&BlockWindowError {
+        height: block.height,
+        time: block.time,
+        min_height,
+        max_height,
+        min_time,
+        message,
+    }))
+}
+
 pub fn block_aggregrate(
     deps: Deps,
     env: Env,
     queries: Vec<Call>,
 ) -> StdResult<BlockAggregateResult> {
-    let block = env.block.height;
     let result = aggregrate(deps, queries)?;
 
     Ok(BlockAggregateResult::from_return_data(
-        block,
+        &env,
         result.return_data,
+        result.executed,
     ))
 }
 
@@ -44,122 +556,780 @@ pub fn block_try_aggregrate(
     env: Env,
     require_success: Option<bool>,
     include_cause: Option<bool>,
+    include_error: Option<bool>,
     queries: Vec<Call>,
+    budget: Option<Budget>,
 ) -> StdResult<BlockAggregateResult> {
-    let block = env.block.height;
-    let result = try_aggregate(deps, require_success, include_cause, queries)?;
+    let result = try_aggregate(
+        deps,
+        require_success,
+        include_cause,
+        include_error,
+        queries,
+        budget,
+    )?;
 
-    Ok(BlockAggregateResult::from_return_data(
-        block,
-        result.return_data,
-    ))
+    let mut block_result =
+        BlockAggregateResult::from_return_data(&env, result.return_data, result.executed);
+    block_result.next_index = result.next_index;
+    Ok(block_result)
 }
 
 pub fn block_try_aggregate_optional(
     deps: Deps,
     env: Env,
     include_cause: Option<bool>,
+    include_error: Option<bool>,
     queries: Vec<CallOptional>,
 ) -> StdResult<BlockAggregateResult> {
-    let block = env.block.height;
-    let result = try_aggregate_optional(deps, include_cause, queries)?;
+    let result = try_aggregate_optional(deps, include_cause, include_error, queries)?;
 
     Ok(BlockAggregateResult::from_return_data(
-        block,
+        &env,
         result.return_data,
+        result.executed,
     ))
 }
 
 pub fn aggregrate(deps: Deps, queries: Vec<Call>) -> StdResult<AggregateResult> {
     let n = queries.len();
+    let mut querier = BatchQuerier::new(deps);
     let mut result: Vec<CallResult> = vec![CallResult::default(); n];
 
     for i in 0..n {
         let query = queries[i].clone();
-        let wasm = &process_wasm_query(query.address, query.data)?;
-        let res = deps.querier.raw_query(wasm);
-        let data = match process_query_result(res) {
+        let wasm = process_wasm_query(query.address.clone(), query.data)?;
+        let at = query.select.is_none().then_some(i);
+        let res = querier.query_at(wasm, at, &result);
+        let data = match res.and_then(|res| process_select(res, &query.select)) {
             Ok(res) => res,
-            Err(err) => return Err(err.std_at_index(i)),
-        };
-        result[i] = CallResult {
-            success: true,
-            data,
+            Err(err) => return Err(err.std_at_index(i, Some(query.address.to_string()))),
         };
+        result[i] = CallResult::from_data(data);
     }
 
-    Ok(AggregateResult::from_return_data(result))
+    Ok(AggregateResult::from_return_data(result, querier.executed))
 }
 
 pub fn try_aggregate(
     deps: Deps,
     require_success: Option<bool>,
     include_cause: Option<bool>,
+    include_error: Option<bool>,
     queries: Vec<Call>,
+    budget: Option<Budget>,
 ) -> StdResult<AggregateResult> {
     let n = queries.len();
+    let budget = budget.unwrap_or_default();
+    let mut querier = BatchQuerier::new(deps);
     let mut result: Vec<CallResult> = vec![CallResult::default(); n];
+    let mut payload = 0u64;
 
     for i in 0..n {
+        if i > 0 && budget_spent(&budget, i as u64, payload) {
+            result.truncate(i);
+            let mut result = AggregateResult::from_return_data(result, querier.executed);
+            result.next_index = Some(i as u64);
+            return Ok(result);
+        }
+
         let query = queries[i].clone();
-        let wasm = &process_wasm_query(query.address, query.data)?;
-        let res = deps.querier.raw_query(wasm);
-        result[i] = match process_query_result(res) {
-            Ok(res) => CallResult {
-                success: true,
-                data: res,
-            },
+        let wasm = process_wasm_query(query.address.clone(), query.data)?;
+        let at = query.select.is_none().then_some(i);
+        let res = querier.query_at(wasm, at, &result);
+        result[i] = match res.and_then(|res| process_select(res, &query.select)) {
+            Ok(res) => CallResult::from_data(res),
             Err(err) => match require_success.unwrap_or(false) {
-                true => return Err(err.std_at_index(i)),
-                false => match include_cause.unwrap_or(false) {
-                    true => CallResult {
-                        success: false,
-                        data: to_binary(&err.to_string())?,
-                    },
-                    false => CallResult {
-                        success: false,
-                        data: Binary::default(),
-                    },
-                },
+                true => return Err(err.std_at_index(i, Some(query.address.to_string()))),
+                false => process_failure(
+                    err,
+                    Some(query.address.to_string()),
+                    include_cause,
+                    include_error,
+                )?,
             },
         };
+        payload += result[i].data.len() as u64;
     }
 
-    Ok(AggregateResult::from_return_data(result))
+    Ok(AggregateResult::from_return_data(result, querier.executed))
 }
 
 pub fn try_aggregate_optional(
     deps: Deps,
     include_cause: Option<bool>,
+    include_error: Option<bool>,
     queries: Vec<CallOptional>,
 ) -> StdResult<AggregateResult> {
     let n = queries.len();
+    let mut querier = BatchQuerier::new(deps);
     let mut result: Vec<CallResult> = vec![CallResult::default(); n];
 
     for i in 0..n {
         let query = queries[i].clone();
-        let wasm = &process_wasm_query(query.address, query.data)?;
-        let res = deps.querier.raw_query(wasm);
-        result[i] = match process_query_result(res) {
-            Ok(res) => CallResult {
+        let wasm = process_wasm_query(query.address.clone(), query.data)?;
+        let at = query.select.is_none().then_some(i);
+        let res = querier.query_at(wasm, at, &result);
+        result[i] = match res.and_then(|res| process_select(res, &query.select)) {
+            Ok(res) => CallResult::from_data(res),
+            Err(err) => match query.require_success {
+                true => return Err(err.std_at_index(i, Some(query.address.to_string()))),
+                false => process_failure(
+                    err,
+                    Some(query.address.to_string()),
+                    include_cause,
+                    include_error,
+                )?,
+            },
+        };
+    }
+
+    Ok(AggregateResult::from_return_data(result, querier.executed))
+}
+
+pub fn map_addresses(
+    deps: Deps,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+    data: Binary,
+    addresses: Vec<Addr>,
+) -> StdResult<AggregateResult> {
+    let queries = addresses
+        .into_iter()
+        .map(|address| Call {
+            address,
+            data: data.clone(),
+            select: None,
+        })
+        .collect();
+
+    try_aggregate(deps, require_success, include_cause, None, queries, None)
+}
+
+pub fn map_values(
+    deps: Deps,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+    address: Addr,
+    template: Binary,
+    values: Vec<String>,
+) -> StdResult<AggregateResult> {
+    let template = json::from_slice(&template)?;
+    let queries = values
+        .iter()
+        .map(|value| {
+            let mut data = template.clone();
+            data.substitute(&mut |s| {
+                StdResult::Ok(process_template_string(s, value).map(Value::String))
+            })?;
+
+            Ok(Call {
+                address: address.clone(),
+                data: Binary::from(data.to_vec()),
+                select: None,
+            })
+        })
+        .collect::<StdResult<Vec<Call>>>()?;
+
+    try_aggregate(deps, require_success, include_cause, None, queries, None)
+}
+
+pub fn block_aggregate_requests(
+    deps: Deps,
+    env: Env,
+    queries: Vec<QueryRequest<Empty>>,
+) -> StdResult<BlockAggregateResult> {
+    let result = aggregate_requests(deps, queries)?;
+
+    Ok(BlockAggregateResult::from_return_data(
+        &env,
+        result.return_data,
+        result.executed,
+    ))
+}
+
+pub fn block_try_aggregate_requests(
+    deps: Deps,
+    env: Env,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+    include_error: Option<bool>,
+    queries: Vec<QueryRequest<Empty>>,
+) -> StdResult<BlockAggregateResult> {
+    let result =
+        try_aggregate_requests(deps, require_success, include_cause, include_error, queries)?;
+
+    Ok(BlockAggregateResult::from_return_data(
+        &env,
+        result.return_data,
+        result.executed,
+    ))
+}
+
+pub fn aggregate_requests(
+    deps: Deps,
+    queries: Vec<QueryRequest<Empty>>,
+) -> StdResult<AggregateResult> {
+    let mut querier = BatchQuerier::new(deps);
+    let mut result: Vec<CallResult> = Vec::with_capacity(queries.len());
+
+    for (i, query) in queries.iter().enumerate() {
+        let res = querier.query_at(to_vec(query)?, Some(i), &result);
+        let data = match res {
+            Ok(res) => res,
+            Err(err) => return Err(err.std_at_index(i, process_request_address(query))),
+        };
+        result.push(CallResult::from_data(data));
+    }
+
+    Ok(AggregateResult::from_return_data(result, querier.executed))
+}
+
+pub fn try_aggregate_requests(
+    deps: Deps,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+    include_error: Option<bool>,
+    queries: Vec<QueryRequest<Empty>>,
+) -> StdResult<AggregateResult> {
+    let mut querier = BatchQuerier::new(deps);
+    let mut result: Vec<CallResult> = Vec::with_capacity(queries.len());
+
+    for (i, query) in queries.iter().enumerate() {
+        let res = querier.query_at(to_vec(query)?, Some(i), &result);
+        result.push(match res {
+            Ok(res) => CallResult::from_data(res),
+            Err(err) => match require_success.unwrap_or(false) {
+                true => return Err(err.std_at_index(i, process_request_address(query))),
+                false => process_failure(
+                    err,
+                    process_request_address(query),
+                    include_cause,
+                    include_error,
+                )?,
+            },
+        });
+    }
+
+    Ok(AggregateResult::from_return_data(result, querier.executed))
+}
+
+pub fn block_aggregate_raw(
+    deps: Deps,
+    env: Env,
+    queries: Vec<RawCall>,
+) -> StdResult<BlockAggregateResult> {
+    let result = aggregate_raw(deps, queries)?;
+
+    Ok(BlockAggregateResult::from_return_data(
+        &env,
+        result.return_data,
+        result.executed,
+    ))
+}
+
+pub fn block_try_aggregate_raw(
+    deps: Deps,
+    env: Env,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+    include_error: Option<bool>,
+    queries: Vec<RawCall>,
+) -> StdResult<BlockAggregateResult> {
+    let result = try_aggregate_raw(deps, require_success, include_cause, include_error, queries)?;
+
+    Ok(BlockAggregateResult::from_return_data(
+        &env,
+        result.return_data,
+        result.executed,
+    ))
+}
+
+pub fn aggregate_raw(deps: Deps, queries: Vec<RawCall>) -> StdResult<AggregateResult> {
+    let requests = queries.into_iter().map(process_raw_query).collect();
+    let result = aggregate_requests(deps, requests)?;
+
+    Ok(process_raw_result(result))
+}
+
+pub fn try_aggregate_raw(
+    deps: Deps,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+    include_error: Option<bool>,
+    queries: Vec<RawCall>,
+) -> StdResult<AggregateResult> {
+    let requests = queries.into_iter().map(process_raw_query).collect();
+    let result = try_aggregate_requests(
+        deps,
+        require_success,
+        include_cause,
+        include_error,
+        requests,
+    )?;
+
+    Ok(process_raw_result(result))
+}
+
+pub fn block_contract_infos(
+    deps: Deps,
+    env: Env,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+    addresses: Vec<Addr>,
+) -> StdResult<BlockContractInfosResult> {
+    let result = contract_infos(deps, require_success, include_cause, addresses)?;
+
+    Ok(BlockContractInfosResult::from_return_data(
+        &env,
+        result.return_data,
+        result.executed,
+    ))
+}
+
+pub fn contract_infos(
+    deps: Deps,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+    addresses: Vec<Addr>,
+) -> StdResult<ContractInfosResult> {
+    let requests = addresses
+        .iter()
+        .map(|address| {
+            QueryRequest::Wasm(WasmQuery::ContractInfo {
+                contract_addr: address.to_string(),
+            })
+        })
+        .collect();
+    let result = try_aggregate_requests(deps, require_success, include_cause, None, requests)?;
+
+    Ok(ContractInfosResult::from_return_data(
+        addresses
+            .into_iter()
+            .zip(result.return_data)
+            .enumerate()
+            .map(|(i, (address, res))| {
+                let (success, info, cause) = process_typed_result(
+                    &res,
+                    i,
+                    Some(address.to_string()),
+                    require_success,
+                    include_cause,
+                )?;
+                Ok(ContractInfoResult {
+                    success,
+                    info,
+                    cause,
+                })
+            })
+            .collect::<StdResult<_>>()?,
+        result.executed,
+    ))
+}
+
+pub fn block_balances(
+    deps: Deps,
+    env: Env,
+    addresses: Vec<Addr>,
+    denoms: Vec<String>,
+) -> StdResult<BlockBalancesResult> {
+    let result = balances(deps, addresses, denoms)?;
+
+    Ok(BlockBalancesResult::from_balances(
+        &env,
+        result.balances,
+        result.executed,
+    ))
+}
+
+pub fn block_all_balances(
+    deps: Deps,
+    env: Env,
+    addresses: Vec<Addr>,
+) -> StdResult<BlockBalancesResult> {
+    let result = all_balances(deps, addresses)?;
+
+    Ok(BlockBalancesResult::from_balances(
+        &env,
+        result.balances,
+        result.executed,
+    ))
+}
+
+pub fn balances(
+    deps: Deps,
+    addresses: Vec<Addr>,
+    denoms: Vec<String>,
+) -> StdResult<BalancesResult> {
+    if denoms.is_empty() {
+        return Ok(BalancesResult::from_balances(
+            vec![vec![]; addresses.len()],
+            0,
+        ));
+    }
+
+    let requests = addresses
+        .iter()
+        .flat_map(|address| {
+            denoms.iter().map(move |denom| {
+                QueryRequest::Bank(BankQuery::Balance {
+                    address: address.to_string(),
+                    denom: denom.clone(),
+                })
+            })
+        })
+        .collect();
+    let result = aggregate_requests(deps, requests)?;
+    let coins = result
+        .return_data
+        .iter()
+        .map(|res| Ok(from_binary::<BalanceResponse>(&res.data)?.amount))
+        .collect::<StdResult<Vec<Coin>>>()?;
+
+    Ok(BalancesResult::from_balances(
+        coins
+            .chunks(denoms.len())
+            .map(|chunk| chunk.to_vec())
+            .collect(),
+        result.executed,
+    ))
+}
+
+pub fn all_balances(deps: Deps, addresses: Vec<Addr>) -> StdResult<BalancesResult> {
+    let requests = addresses
+        .into_iter()
+        .map(|address| {
+            QueryRequest::Bank(BankQuery::AllBalances {
+                address: address.to_string(),
+            })
+        })
+        .collect();
+    let result = aggregate_requests(deps, requests)?;
+
+    Ok(BalancesResult::from_balances(
+        result
+            .return_data
+            .iter()
+            .map(|res| Ok(from_binary::<AllBalanceResponse>(&res.data)?.amount))
+            .collect::<StdResult<_>>()?,
+        result.executed,
+    ))
+}
+
+pub fn cw20_balances(
+    deps: Deps,
+    require_success: Option<bool>,
+    include_cause: Option<bool>,
+    tokens: Vec<Addr>,
+    holders: Vec<Addr>,
+) -> StdResult<Cw20BalancesResult> {
+    let pairs = tokens
+        .iter()
+        .flat_map(|token| holders.iter().map(move |holder| (token, holder)))
+        .collect::<Vec<_>>();
+    let queries = pairs
+        .iter()
+        .map(|(token, holder)| {
+            Ok(Call {
+                address: (*token).clone(),
+                data: to_binary(&Cw20QueryMsg::Balance {
+                    address: holder.to_string(),
+                })?,
+                select: None,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    let result = try_aggregate(deps, require_success, include_cause, None, queries, None)?;
+
+    Ok(Cw20BalancesResult::from_return_data(
+        pairs
+            .into_iter()
+            .zip(result.return_data)
+            .enumerate()
+            .map(|(i, ((token, holder), res))| {
+                let (success, response, cause) = process_typed_result::<Cw20BalanceResponse>(
+                    &res,
+                    i,
+                    Some(token.to_string()),
+                    require_success,
+                    include_cause,
+                )?;
+                Ok(Cw20BalanceResult {
+                    token: token.clone(),
+                    holder: holder.clone(),
+                    success,
+                    balance: response.map(|response| response.balance),
+                    cause,
+                })
+            })
+            .collect::<StdResult<_>>()?,
+        result.executed,
+    ))
+}
+
+pub fn cw721_batch(
+    deps: Deps,
+    include_cause: Option<bool>,
+    include_expired: Option<bool>,
+    queries: Vec<Cw721Call>,
+) -> StdResult<Cw721BatchResult> {
+    let mut querier = BatchQuerier::new(deps);
+    let mut result: Vec<Cw721Result> = Vec::with_capacity(queries.len());
+
+    for (i, query) in queries.into_iter().enumerate() {
+        let owner_of = process_wasm_query(
+            query.collection.clone(),
+            to_binary(&Cw721QueryMsg::OwnerOf {
+                token_id: query.token_id.clone(),
+                include_expired,
+            })?,
+        )?;
+        let nft_info = process_wasm_query(
+            query.collection.clone(),
+            to_binary(&Cw721QueryMsg::NftInfo {
+                token_id: query.token_id.clone(),
+            })?,
+        )?;
+        let res = querier.query(owner_of).and_then(|owner| {
+            let info = querier.query(nft_info)?;
+            let decode = |err: StdError| QueryError::Contract(err.to_string());
+            Ok((
+                from_binary::<OwnerOfResponse>(&owner).map_err(decode)?,
+                from_binary::<NftInfoResponse>(&info).map_err(decode)?,
+            ))
+        });
+        result.push(match res {
+            Ok((owner, info)) => Cw721Result {
+                collection: query.collection,
+                token_id: query.token_id,
                 success: true,
-                data: res,
+                owner: Some(owner.owner),
+                approvals: Some(owner.approvals),
+                token_uri: info.token_uri,
+                cause: None,
             },
             Err(err) => match query.require_success {
-                true => return Err(err.std_at_index(i)),
-                false => match include_cause.unwrap_or(false) {
-                    true => CallResult {
-                        success: false,
-                        data: to_binary(&err.to_string())?,
-                    },
-                    false => CallResult {
-                        success: false,
-                        data: Binary::default(),
+                true => return Err(err.std_at_index(i, Some(query.collection.to_string()))),
+                false => Cw721Result {
+                    collection: query.collection,
+                    token_id: query.token_id,
+                    success: false,
+                    owner: None,
+                    approvals: None,
+                    token_uri: None,
+                    cause: match include_cause.unwrap_or(false) {
+                        true => Some(err.to_string()),
+                        false => None,
                     },
                 },
             },
+        });
+    }
+
+    Ok(Cw721BatchResult::from_return_data(result, querier.executed))
+}
+
+pub fn guarded_aggregate(deps: Deps, queries: Vec<CallKind>) -> StdResult<AggregateResult> {
+    let mut querier = BatchQuerier::new(deps);
+    let mut result: Vec<CallResult> = Vec::with_capacity(queries.len());
+
+    for (i, query) in queries.into_iter().enumerate() {
+        let (address, res) = match query {
+            CallKind::Query(query) => (
+                Some(query.address.to_string()),
+                querier
+                    .query_at(
+                        process_wasm_query(query.address, query.data)?,
+                        query.select.is_none().then_some(i),
+                        &result,
+                    )
+                    .and_then(|res| process_select(res, &query.select)),
+            ),
+            CallKind::Assert(assertion) => (
+                process_assertion_address(&assertion),
+                process_assertion(&mut querier, &result, &assertion)?,
+            ),
+        };
+        match res {
+            Ok(data) => result.push(CallResult::from_data(data)),
+            Err(err) => return Err(err.std_at_index(i, address)),
+        }
+    }
+
+    Ok(AggregateResult::from_return_data(result, querier.executed))
+}
+
+/// Checks assertions outside of a query batch, so `AssertSource::Index` never resolves.
+pub fn assert(deps: Deps, assertions: &[Assertion]) -> StdResult<()> {
+    let mut querier = BatchQuerier::new(deps);
+
+    for (i, assertion) in assertions.iter().enumerate() {
+        if let Err(err) = process_assertion(&mut querier, &[], assertion)? {
+            return Err(err.std_at_index(i, process_assertion_address(assertion)));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn reduce(
+    deps: Deps,
+    queries: Vec<Call>,
+    path: String,
+    include_results: Option<bool>,
+) -> StdResult<ReduceResult> {
+    let addresses: Vec<Addr> = queries.iter().map(|query| query.address.clone()).collect();
+    let result = aggregrate(deps, queries)?;
+
+    let mut values = result
+        .return_data
+        .iter()
+        .enumerate()
+        .map(|(i, call)| {
+            process_decimal(&call.data, &path)
+                .map_err(|err| err.std_at_index(i, Some(addresses[i].to_string())))
+        })
+        .collect::<StdResult<Vec<Decimal256>>>()?;
+    if values.is_empty() {
+        return Err(StdError::generic_err("Nothing to reduce"));
+    }
+    values.sort();
+
+    let n = values.len();
+    let sum = checked_sum(&values)?;
+
+    Ok(ReduceResult {
+        count: n as u64,
+        sum,
+        min: values[0],
+        max: values[n - 1],
+        mean: sum / Uint256::from(n as u128),
+        median: median(&values),
+        return_data: match include_results.unwrap_or(false) {
+            true => Some(result.return_data),
+            false => None,
+        },
+    })
+}
+
+pub fn oracle_price(
+    deps: Deps,
+    env: Env,
+    oracles: Vec<OracleSource>,
+    max_age: u64,
+) -> StdResult<OraclePriceResult> {
+    let queries = oracles
+        .iter()
+        .map(|oracle| Call {
+            address: oracle.address.clone(),
+            data: oracle.data.clone(),
+            select: None,
+        })
+        .collect();
+    let result = try_aggregate(deps, None, None, None, queries, None)?;
+    let now = env.block.time.seconds();
+
+    let mut sources: Vec<OraclePriceSource> = vec![];
+    for (oracle, call) in oracles.into_iter().zip(result.return_data) {
+        if !call.success {
+            continue;
+        }
+
+        let price = process_decimal(&call.data, &oracle.price_path);
+        let time = process_seconds(&call.data, &oracle.time_path);
+        if let (Ok(price), Ok(time)) = (price, time) {
+            // A time ahead of the block, e.g. one in nanoseconds, cannot be trusted as fresh.
+            if time > now || now - time > max_age {
+                continue;
+            }
+            sources.push(OraclePriceSource {
+                address: oracle.address,
+                price,
+                time: Timestamp::from_seconds(time),
+            });
+        }
+    }
+    if sources.is_empty() {
+        return Err(StdError::generic_err("No fresh oracle price"));
+    }
+
+    let mut prices: Vec<Decimal256> = sources.iter().map(|source| source.price).collect();
+    prices.sort();
+
+    Ok(OraclePriceResult {
+        median: median(&prices),
+        sources,
+    })
+}
+
+pub fn paginate(
+    deps: Deps,
+    address: Addr,
+    data: Binary,
+    start_after: String,
+    items: String,
+    cursor: Option<String>,
+    max_pages: u64,
+) -> StdResult<PaginateResult> {
+    if max_pages == 0 {
+        return Err(StdError::generic_err("max_pages must be at least 1"));
+    }
+
+    let mut template = json::from_slice(&data)?;
+    let mut result: Vec<Value> = vec![];
+    let mut next_start_after: Option<Value> = None;
+    let mut pages = 0u64;
+
+    while pages < max_pages {
+        let i = pages as usize;
+        if let Some(next) = next_start_after.take() {
+            template.set(&start_after, next).ok_or_else(|| {
+                QueryError::Placeholder(start_after.clone(), String::from("path not found in data"))
+                    .std_at_index(i, Some(address.to_string()))
+            })?;
+        }
+
+        let wasm = process_wasm_query(address.clone(), Binary::from(template.to_vec()))?;
+        // Pages never repeat while the cursor moves, so they are not cached.
+        let page = process_query_result(deps.querier.raw_query(&wasm))
+            .and_then(|res| process_page(&res, &items, &cursor))
+            .map_err(|err| err.std_at_index(i, Some(address.to_string())))?;
+        pages += 1;
+
+        match page {
+            Some((page_items, next)) => {
+                result.extend(page_items);
+                next_start_after = Some(next);
+            }
+            None => break,
+        }
+    }
+
+    Ok(PaginateResult {
+        items: Binary::from(Value::Array(result).to_vec()),
+        pages,
+        next_start_after: next_start_after.map(|next| Binary::from(next.to_vec())),
+    })
+}
+
+pub fn chain(deps: Deps, queries: Vec<Call>) -> StdResult<AggregateResult> {
+    let mut querier = BatchQuerier::new(deps);
+    let mut result: Vec<CallResult> = Vec::with_capacity(queries.len());
+
+    for (i, query) in queries.into_iter().enumerate() {
+        let query = match process_chain_call(&result, query) {
+            Ok(query) => query,
+            Err(err) => return Err(err.std_at_index(i, None)),
+        };
+        let wasm = process_wasm_query(query.address.clone(), query.data)?;
+        let at = query.select.is_none().then_some(i);
+        let res = querier.query_at(wasm, at, &result);
+        let data = match res.and_then(|res| process_select(res, &query.select)) {
+            Ok(res) => res,
+            Err(err) => return Err(err.std_at_index(i, Some(query.address.to_string()))),
         };
+        result.push(CallResult::from_data(data));
     }
 
-    Ok(AggregateResult::from_return_data(result))
+    Ok(AggregateResult::from_return_data(result, querier.executed))
 }
@@ -0,0 +1,21 @@
+use cosmwasm_std::{Addr, Coin};
+use cw_storage_plus::Item;
+use serde::{Deserialize, Serialize};
+
+use crate::msg::CallResult;
+
+/// Execute batch awaiting the replies of its sub messages.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PendingBatch {
+    pub sender: Addr,
+    pub include_cause: bool,
+    /// Messages whose failure aborts the whole batch.
+    pub require_success: Vec<bool>,
+    /// Funds each message spends, refunded when the message fails.
+    pub funds: Vec<Vec<Coin>>,
+    /// Funds sent but not spent by any message, refunded once the batch completes.
+    pub refund: Vec<Coin>,
+    pub return_data: Vec<CallResult>,
+}
+
+pub const PENDING_BATCH: Item<PendingBatch> = Item::new("pending_batch");
@@ -1,13 +1,40 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, str::FromStr};
 
 use crate::{
-    contract::query,
-    mock_querier::{mock_dependencies, AnotherStructResponse, MockQueryMsg},
-    msg::{AggregateResult, BlockAggregateResult, Call, CallOptional, QueryMsg},
+    contract::{execute, query, reply},
+    cw20::{Cw20BalanceResponse, Cw20QueryMsg},
+    error::ContractError,
+    json,
+    mock_querier::{
+        mock_dependencies, mock_dependencies_with_balances, AnotherStructResponse, MockAccount,
+        MockQueryMsg, SomeStructResponse, MOCK_ACCOUNTS, MOCK_BURNED_TOKEN,
+        MOCK_MALFORMED_CONTRACT, MOCK_MISSING_CONTRACT, MOCK_MISSING_KEY,
+    },
+    msg::{
+        AggregateError, AggregateResult, AssertSource, Assertion, BalancesResult,
+        BlockAggregateResult, BlockBalancesResult, BlockContractInfosResult, BlockWindowError,
+        Budget, Call, CallError, CallKind, CallOptional, CallResult, CompareOp,
+        ContractInfosResult, Cw20BalancesResult, Cw721BatchResult, Cw721Call, ErrorKind,
+        ExecuteMsg, MsgOptional, OraclePriceResult, OracleSource, PaginateResult, QueryMsg,
+        RawCall, ReduceResult, ResponseFormat,
+    },
+};
+use cosmwasm_std::{
+    attr, coin, from_binary, from_slice,
+    testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+    to_binary, Addr, BalanceResponse, BankMsg, BankQuery, Binary, BlockInfo, Coin, CosmosMsg,
+    Decimal256, DistributionMsg, Empty, Env, QueryRequest, Reply, ReplyOn, StakingMsg, StdError,
+    SubMsg, SubMsgResponse, SubMsgResult, Timestamp, Uint128, WasmMsg, WasmQuery,
 };
-use cosmwasm_std::{from_binary, testing::mock_env, to_binary, Addr, BlockInfo, Env, StdError};
 use test_case::test_case;
 
+fn aggregate_error(err: &StdError) -> AggregateError {
+    match err {
+        StdError::GenericErr { msg } => from_slice(msg.as_bytes()).unwrap(),
+        err => panic!("unexpected error {}", err),
+    }
+}
+
 fn env_with_height(height: u64) -> Env {
     let mock = mock_env();
 
@@ -34,7 +61,13 @@ fn block_aggregate(x: u64) {
             queries: vec![Call {
                 address: Addr::unchecked(""),
                 data: to_binary(&MockQueryMsg::FailSystem).unwrap(),
+                select: None,
             }],
+            response_format: None,
+            min_height: None,
+            max_height: None,
+            min_time: None,
+            max_response_bytes: None,
         },
     )
     .unwrap_err();
@@ -49,7 +82,13 @@ fn block_aggregate(x: u64) {
                 queries: vec![Call {
                     address: Addr::unchecked(""),
                     data: to_binary(&MockQueryMsg::One).unwrap(),
+                    select: None,
                 }],
+                response_format: None,
+                min_height: None,
+                max_height: None,
+                min_time: None,
+                max_response_bytes: None,
             },
         )
         .unwrap(),
@@ -57,6 +96,9 @@ fn block_aggregate(x: u64) {
     .unwrap();
 
     assert_eq!(q.block, x);
+    assert_eq!(q.time, env.block.time);
+    assert_eq!(q.chain_id, env.block.chain_id);
+    assert_eq!(q.contract, env.contract.address);
     assert_eq!(
         base64::encode(b"1"),
         q.return_data.first().unwrap().data.to_base64()
@@ -73,7 +115,15 @@ fn block_aggregate(x: u64) {
                 queries: vec![Call {
                     address: Addr::unchecked(""),
                     data: to_binary(&MockQueryMsg::One).unwrap(),
+                    select: None,
                 }],
+                response_format: None,
+                min_height: None,
+                max_height: None,
+                min_time: None,
+                include_error: None,
+                budget: None,
+                max_response_bytes: None,
             },
         )
         .unwrap(),
@@ -97,7 +147,14 @@ fn block_aggregate(x: u64) {
                     require_success: false,
                     address: Addr::unchecked(""),
                     data: to_binary(&MockQueryMsg::One).unwrap(),
+                    select: None,
                 }],
+                response_format: None,
+                min_height: None,
+                max_height: None,
+                min_time: None,
+                include_error: None,
+                max_response_bytes: None,
             },
         )
         .unwrap(),
@@ -129,7 +186,10 @@ fn aggregate(x: &str) {
             queries: vec![Call {
                 address: Addr::unchecked(""),
                 data: to_binary(&MockQueryMsg::FailSystem).unwrap(),
+                select: None,
             }],
+            response_format: None,
+            max_response_bytes: None,
         },
     )
     .unwrap_err();
@@ -144,7 +204,10 @@ fn aggregate(x: &str) {
                 queries: vec![Call {
                     address: Addr::unchecked(""),
                     data: to_binary(&MockQueryMsg::Str(x.to_string())).unwrap(),
+                    select: None,
                 }],
+                response_format: None,
+                max_response_bytes: None,
             },
         )
         .unwrap(),
@@ -166,12 +229,16 @@ fn aggregate(x: &str) {
                     Call {
                         address: Addr::unchecked(""),
                         data: to_binary(&MockQueryMsg::Str(x.to_string())).unwrap(),
+                        select: None,
                     },
                     Call {
                         address: Addr::unchecked(""),
                         data: to_binary(&MockQueryMsg::Str(x.to_string())).unwrap(),
+                        select: None,
                     },
                 ],
+                response_format: None,
+                max_response_bytes: None,
             },
         )
         .unwrap(),
@@ -211,12 +278,16 @@ fn aggregate_struct(x: &str) {
                     Call {
                         address: Addr::unchecked(""),
                         data: to_binary(&MockQueryMsg::StructStr(x.to_string())).unwrap(),
+                        select: None,
                     },
                     Call {
                         address: Addr::unchecked(""),
                         data: to_binary(&MockQueryMsg::StructStr(x.to_string())).unwrap(),
+                        select: None,
                     },
                 ],
+                response_format: None,
+                max_response_bytes: None,
             },
         )
         .unwrap(),
@@ -251,6 +322,7 @@ fn try_aggregate(total: usize, error_at: Vec<usize>) {
                 _ => MockQueryMsg::One,
             })
             .unwrap(),
+            select: None,
         })
         .collect::<Vec<_>>();
 
@@ -262,6 +334,10 @@ fn try_aggregate(total: usize, error_at: Vec<usize>) {
                 require_success: Some(false),
                 queries: body.clone(),
                 include_cause: Some(false),
+                response_format: None,
+                include_error: None,
+                budget: None,
+                max_response_bytes: None,
             },
         )
         .unwrap(),
@@ -285,6 +361,10 @@ fn try_aggregate(total: usize, error_at: Vec<usize>) {
             require_success: Some(true),
             queries: body,
             include_cause: Some(false),
+            response_format: None,
+            include_error: None,
+            budget: None,
+            max_response_bytes: None,
         },
     );
 
@@ -314,6 +394,7 @@ fn try_aggregate_optional(total: usize, error_at: Vec<usize>, required: Vec<usiz
                 _ => MockQueryMsg::One,
             })
             .unwrap(),
+            select: None,
         })
         .collect::<Vec<_>>();
 
@@ -324,6 +405,9 @@ fn try_aggregate_optional(total: usize, error_at: Vec<usize>, required: Vec<usiz
             QueryMsg::TryAggregateOptional {
                 include_cause: Some(false),
                 queries: body,
+                response_format: None,
+                include_error: None,
+                max_response_bytes: None,
             },
         )
         .unwrap(),
@@ -349,6 +433,7 @@ fn try_aggregate_optional(total: usize, error_at: Vec<usize>, required: Vec<usiz
                 _ => MockQueryMsg::One,
             })
             .unwrap(),
+            select: None,
         })
         .collect::<Vec<_>>();
 
@@ -358,16 +443,2345 @@ fn try_aggregate_optional(total: usize, error_at: Vec<usize>, required: Vec<usiz
         QueryMsg::TryAggregateOptional {
             include_cause: Some(false),
             queries: body,
+            response_format: None,
+            include_error: None,
+            max_response_bytes: None,
         },
     );
 
     let err_hs = error_at.iter().collect::<HashSet<_>>();
     let rq_hs = required.iter().collect::<HashSet<_>>();
 
-    match err_hs.intersection(&rq_hs).into_iter().next() {
+    match err_hs.intersection(&rq_hs).next() {
         Some(_) => assert!(matches!(q.unwrap_err(), StdError::GenericErr { msg: _ })),
         None => assert!(
             matches!(from_binary::<AggregateResult>(&q.unwrap()).unwrap(), x if x.return_data.len() == total)
         ),
     }
 }
+
+fn smart_request(msg: &MockQueryMsg) -> QueryRequest<Empty> {
+    QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: String::new(),
+        msg: to_binary(msg).unwrap(),
+    })
+}
+
+fn balance_request(address: &str, denom: &str) -> QueryRequest<Empty> {
+    QueryRequest::Bank(BankQuery::Balance {
+        address: address.to_string(),
+        denom: denom.to_string(),
+    })
+}
+
+#[test_case(0; "empty balance")]
+#[test_case(1; "one")]
+#[test_case(100_000_000; "large balance")]
+fn aggregate_requests(amount: u128) {
+    let deps = mock_dependencies(&[coin(amount, "uluna")]);
+    let env = env_with_height(42);
+
+    let q: BlockAggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BlockAggregateRequests {
+                queries: vec![
+                    balance_request(MOCK_CONTRACT_ADDR, "uluna"),
+                    smart_request(&MockQueryMsg::One),
+                    balance_request("nobody", "uluna"),
+                ],
+                response_format: None,
+                min_height: None,
+                max_height: None,
+                min_time: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.block, 42);
+    assert_eq!(q.return_data.len(), 3);
+    assert!(q.return_data.iter().all(|r| r.success));
+
+    let balance: BalanceResponse = from_binary(&q.return_data[0].data).unwrap();
+    assert_eq!(balance.amount, coin(amount, "uluna"));
+    assert_eq!(base64::encode(b"1"), q.return_data[1].data.to_base64());
+    let balance: BalanceResponse = from_binary(&q.return_data[2].data).unwrap();
+    assert_eq!(balance.amount, coin(0, "uluna"));
+
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::AggregateRequests {
+            queries: vec![
+                balance_request(MOCK_CONTRACT_ADDR, "uluna"),
+                smart_request(&MockQueryMsg::FailContract),
+            ],
+            response_format: None,
+            max_response_bytes: None,
+        },
+    )
+    .unwrap_err();
+
+    let err = aggregate_error(&err);
+    assert_eq!((err.index, err.kind), (1, ErrorKind::Contract));
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::TryAggregateRequests {
+                require_success: None,
+                include_cause: Some(true),
+                queries: vec![
+                    smart_request(&MockQueryMsg::FailContract),
+                    balance_request(MOCK_CONTRACT_ADDR, "uluna"),
+                ],
+                response_format: None,
+                include_error: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert!(!q.return_data[0].success);
+    assert_eq!(
+        from_binary::<String>(&q.return_data[0].data).unwrap(),
+        "Querier contract error: error"
+    );
+    assert!(q.return_data[1].success);
+
+    let err = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BlockTryAggregateRequests {
+            require_success: Some(true),
+            include_cause: None,
+            queries: vec![smart_request(&MockQueryMsg::FailSystem)],
+            response_format: None,
+            min_height: None,
+            max_height: None,
+            min_time: None,
+            include_error: None,
+            max_response_bytes: None,
+        },
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, StdError::GenericErr { msg: _ }));
+}
+
+fn raw_call(address: &str, key: &[u8]) -> RawCall {
+    RawCall {
+        address: Addr::unchecked(address),
+        key: Binary::from(key),
+    }
+}
+
+#[test_case(b"config"; "item key")]
+#[test_case(b"\x00\x08balances"; "map namespace")]
+#[test_case(b"contract_info"; "cw2 key")]
+fn aggregate_raw(key: &[u8]) {
+    let deps = mock_dependencies(&[]);
+    let env = env_with_height(7);
+
+    let q: BlockAggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BlockAggregateRaw {
+                queries: vec![raw_call("token", key), raw_call("token", MOCK_MISSING_KEY)],
+                response_format: None,
+                min_height: None,
+                max_height: None,
+                min_time: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.block, 7);
+    assert!(q.return_data.iter().all(|r| r.success));
+    assert_eq!(q.return_data[0].data.as_slice(), [b"value:", key].concat());
+    assert_eq!(q.return_data[0].found, Some(true));
+    assert!(q.return_data[1].data.is_empty());
+    assert_eq!(q.return_data[1].found, Some(false));
+
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::AggregateRaw {
+            queries: vec![raw_call("token", key), raw_call(MOCK_MISSING_CONTRACT, key)],
+            response_format: None,
+            max_response_bytes: None,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        aggregate_error(&err),
+        AggregateError {
+            index: 1,
+            kind: ErrorKind::System,
+            system_error: Some(String::from("no_such_contract")),
+            address: Some(MOCK_MISSING_CONTRACT.to_string()),
+            message: format!("No such contract: {}", MOCK_MISSING_CONTRACT),
+        }
+    );
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::TryAggregateRaw {
+                require_success: Some(false),
+                include_cause: Some(false),
+                queries: vec![raw_call(MOCK_MISSING_CONTRACT, key), raw_call("token", key)],
+                response_format: None,
+                include_error: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert!(!q.return_data[0].success);
+    assert_eq!(q.return_data[0].found, None);
+    assert!(q.return_data[1].success);
+    assert_eq!(q.return_data[1].found, Some(true));
+}
+
+#[test]
+fn call_result_omits_found() {
+    let q: AggregateResult = from_binary(
+        &query(
+            mock_dependencies(&[]).as_ref(),
+            mock_env(),
+            QueryMsg::Aggregate {
+                queries: vec![Call {
+                    address: Addr::unchecked(""),
+                    data: to_binary(&MockQueryMsg::One).unwrap(),
+                    select: None,
+                }],
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        to_binary(&q).unwrap(),
+        Binary::from(br#"{"return_data":[{"success":true,"data":"MQ=="}],"executed":1}"#)
+    );
+}
+
+#[test_case(vec!["a", "bb", "ccc"], vec![]; "no error")]
+#[test_case(vec!["a", "bb", "ccc"], vec![1]; "error in the middle")]
+#[test_case(vec!["a", "bb"], vec![0, 1]; "all error")]
+fn contract_infos(addresses: Vec<&str>, missing_at: Vec<usize>) {
+    let deps = mock_dependencies(&[]);
+    let env = env_with_height(11);
+
+    let addresses = addresses
+        .iter()
+        .enumerate()
+        .map(|(i, address)| match i {
+            _ if missing_at.contains(&i) => Addr::unchecked(MOCK_MISSING_CONTRACT),
+            _ => Addr::unchecked(*address),
+        })
+        .collect::<Vec<_>>();
+
+    let q: BlockContractInfosResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BlockContractInfos {
+                require_success: None,
+                include_cause: Some(true),
+                addresses: addresses.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.block, 11);
+    assert_eq!(q.chain_id, env.block.chain_id);
+    assert_eq!(q.contract, env.contract.address);
+    assert_eq!(q.return_data.len(), addresses.len());
+    for (i, res) in q.return_data.iter().enumerate() {
+        match i {
+            _ if missing_at.contains(&i) => {
+                assert!(!res.success);
+                assert!(res.info.is_none());
+                assert!(res
+                    .cause
+                    .as_ref()
+                    .unwrap()
+                    .starts_with("Querier system error"));
+            }
+            _ => {
+                let info = res.info.as_ref().unwrap();
+                assert!(res.success);
+                assert_eq!(info.code_id, addresses[i].as_str().len() as u64);
+                assert_eq!(info.admin, Some(format!("{}_admin", addresses[i])));
+                assert!(res.cause.is_none());
+            }
+        }
+    }
+
+    let q: ContractInfosResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::ContractInfos {
+                require_success: None,
+                include_cause: None,
+                addresses: addresses.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert!(q.return_data.iter().all(|res| res.cause.is_none()));
+
+    let q = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::ContractInfos {
+            require_success: Some(true),
+            include_cause: None,
+            addresses,
+        },
+    );
+
+    match missing_at[..] {
+        [] => assert!(q.is_ok()),
+        _ => assert!(matches!(q.unwrap_err(), StdError::GenericErr { msg: _ })),
+    }
+}
+
+#[test_case(vec![], vec!["uluna"]; "no address")]
+#[test_case(vec!["alice"], vec![]; "no denom")]
+#[test_case(vec!["alice", "bob", "carol"], vec!["uluna"]; "many addresses")]
+#[test_case(vec!["alice", "bob", "carol"], vec!["uluna", "uusd", "ukrw"]; "many addresses and denoms")]
+fn balances(addresses: Vec<&str>, denoms: Vec<&str>) {
+    let alice = [coin(100, "uluna"), coin(5, "uusd")];
+    let bob = [coin(42, "ukrw")];
+    let deps = mock_dependencies_with_balances(&[("alice", &alice), ("bob", &bob)]);
+    let env = env_with_height(3);
+
+    let addresses = addresses
+        .into_iter()
+        .map(Addr::unchecked)
+        .collect::<Vec<_>>();
+    let denoms = denoms.into_iter().map(String::from).collect::<Vec<_>>();
+
+    let q: BlockBalancesResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BlockBalances {
+                addresses: addresses.clone(),
+                denoms: denoms.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.block, 3);
+    assert_eq!(q.chain_id, env.block.chain_id);
+    assert_eq!(q.contract, env.contract.address);
+    assert_eq!(q.balances.len(), addresses.len());
+    for (address, coins) in addresses.iter().zip(q.balances) {
+        let expected = match address.as_str() {
+            "alice" => &alice[..],
+            "bob" => &bob[..],
+            _ => &[],
+        };
+        assert_eq!(coins.len(), denoms.len());
+        for (denom, coin) in denoms.iter().zip(coins) {
+            assert_eq!(&coin.denom, denom);
+            assert_eq!(
+                coin.amount,
+                expected
+                    .iter()
+                    .find(|c| &c.denom == denom)
+                    .map(|c| c.amount)
+                    .unwrap_or_default()
+            );
+        }
+    }
+
+    let q: BalancesResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::AllBalances {
+                addresses: addresses.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.balances.len(), addresses.len());
+    for (address, coins) in addresses.iter().zip(q.balances) {
+        match address.as_str() {
+            "alice" => assert_eq!(coins, alice),
+            "bob" => assert_eq!(coins, bob),
+            _ => assert!(coins.is_empty()),
+        }
+    }
+}
+
+#[test_case(vec!["token"], vec!["alice", "bob"], vec![]; "one token many holders")]
+#[test_case(vec!["token", "other_token"], vec!["alice"], vec![]; "many tokens one holder")]
+#[test_case(vec!["token", "bad", "other_token"], vec!["alice", "bob"], vec![1]; "invalid token")]
+#[test_case(vec![], vec!["alice"], vec![]; "no token")]
+fn cw20_balances(tokens: Vec<&str>, holders: Vec<&str>, missing_at: Vec<usize>) {
+    let deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let tokens = tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| match i {
+            _ if missing_at.contains(&i) => Addr::unchecked(MOCK_MISSING_CONTRACT),
+            _ => Addr::unchecked(*token),
+        })
+        .collect::<Vec<_>>();
+    let holders = holders.into_iter().map(Addr::unchecked).collect::<Vec<_>>();
+
+    let q: Cw20BalancesResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Cw20Balances {
+                require_success: None,
+                include_cause: Some(true),
+                tokens: tokens.clone(),
+                holders: holders.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.return_data.len(), tokens.len() * holders.len());
+    for (i, res) in q.return_data.iter().enumerate() {
+        assert_eq!(res.token, tokens[i / holders.len()]);
+        assert_eq!(res.holder, holders[i % holders.len()]);
+        match res.token.as_str() {
+            MOCK_MISSING_CONTRACT => {
+                assert!(!res.success);
+                assert!(res.balance.is_none());
+                assert!(res.cause.is_some());
+            }
+            token => {
+                assert!(res.success);
+                assert_eq!(
+                    res.balance,
+                    Some(Uint128::from(
+                        (token.len() * 1000 + res.holder.as_str().len()) as u64
+                    ))
+                );
+            }
+        }
+    }
+
+    let q = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Cw20Balances {
+            require_success: Some(true),
+            include_cause: None,
+            tokens,
+            holders,
+        },
+    );
+
+    match missing_at[..] {
+        [] => assert!(q.is_ok()),
+        _ => assert!(matches!(q.unwrap_err(), StdError::GenericErr { msg: _ })),
+    }
+}
+
+#[test_case(None; "suppressed")]
+#[test_case(Some(true); "required")]
+fn cw20_balances_malformed(require_success: Option<bool>) {
+    let deps = mock_dependencies(&[]);
+
+    let q = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Cw20Balances {
+            require_success,
+            include_cause: Some(true),
+            tokens: vec![
+                Addr::unchecked("token"),
+                Addr::unchecked(MOCK_MALFORMED_CONTRACT),
+            ],
+            holders: vec![Addr::unchecked("alice")],
+        },
+    );
+
+    if require_success.is_some() {
+        let err = aggregate_error(&q.unwrap_err());
+        assert_eq!(err.index, 1);
+        assert_eq!(err.kind, ErrorKind::Contract);
+        assert_eq!(err.address, Some(MOCK_MALFORMED_CONTRACT.to_string()));
+        return;
+    }
+
+    let q: Cw20BalancesResult = from_binary(&q.unwrap()).unwrap();
+    assert!(q.return_data[0].success);
+    assert!(!q.return_data[1].success);
+    assert!(q.return_data[1].balance.is_none());
+    assert!(q.return_data[1]
+        .cause
+        .as_ref()
+        .unwrap()
+        .starts_with("Querier contract error: Error parsing"));
+}
+
+#[test_case(None; "suppressed")]
+#[test_case(Some(true); "required")]
+fn contract_infos_malformed(require_success: Option<bool>) {
+    let deps = mock_dependencies(&[]);
+
+    let q = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ContractInfos {
+            require_success,
+            include_cause: None,
+            addresses: vec![
+                Addr::unchecked(MOCK_MALFORMED_CONTRACT),
+                Addr::unchecked("contract"),
+            ],
+        },
+    );
+
+    if require_success.is_some() {
+        let err = aggregate_error(&q.unwrap_err());
+        assert_eq!(err.index, 0);
+        assert_eq!(err.address, Some(MOCK_MALFORMED_CONTRACT.to_string()));
+        return;
+    }
+
+    let q: ContractInfosResult = from_binary(&q.unwrap()).unwrap();
+    assert!(!q.return_data[0].success);
+    assert!(q.return_data[0].info.is_none());
+    assert!(q.return_data[0].cause.is_none());
+    assert!(q.return_data[1].success);
+}
+
+#[test_case(vec![], vec![]; "no error")]
+#[test_case(vec![1], vec![]; "burned token")]
+#[test_case(vec![0, 2], vec![2]; "burned required token")]
+#[test_case(vec![0], vec![1]; "required token exists")]
+fn cw721_batch(burned_at: Vec<usize>, required: Vec<usize>) {
+    let deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let queries = (0..3)
+        .map(|i| Cw721Call {
+            require_success: required.contains(&i),
+            collection: Addr::unchecked(format!("collection{}", i)),
+            token_id: match i {
+                _ if burned_at.contains(&i) => MOCK_BURNED_TOKEN.to_string(),
+                _ => i.to_string(),
+            },
+        })
+        .collect::<Vec<_>>();
+
+    let q = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Cw721Batch {
+            include_cause: Some(true),
+            include_expired: Some(true),
+            queries: queries.clone(),
+        },
+    );
+
+    if burned_at.iter().any(|i| required.contains(i)) {
+        assert!(matches!(q.unwrap_err(), StdError::GenericErr { msg: _ }));
+        return;
+    }
+
+    let q: Cw721BatchResult = from_binary(&q.unwrap()).unwrap();
+
+    assert_eq!(q.return_data.len(), queries.len());
+    for (i, res) in q.return_data.iter().enumerate() {
+        assert_eq!(res.collection, queries[i].collection);
+        assert_eq!(res.token_id, queries[i].token_id);
+        match i {
+            _ if burned_at.contains(&i) => {
+                assert!(!res.success);
+                assert!(res.owner.is_none());
+                assert_eq!(
+                    res.cause,
+                    Some(String::from(
+                        "Querier contract error: cw721_base::state::TokenInfo not found"
+                    ))
+                );
+            }
+            _ => {
+                assert!(res.success);
+                assert_eq!(res.owner, Some(format!("{}_owner", i)));
+                assert_eq!(res.approvals.as_ref().unwrap().len(), 1);
+                assert_eq!(res.token_uri, Some(format!("ipfs://collection{}/{}", i, i)));
+                assert!(res.cause.is_none());
+            }
+        }
+    }
+
+    let q: Cw721BatchResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Cw721Batch {
+                include_cause: None,
+                include_expired: None,
+                queries,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    for res in q.return_data {
+        assert!(res.cause.is_none());
+        assert!(res.approvals.unwrap_or_default().is_empty());
+    }
+}
+
+#[test]
+fn cw721_batch_deduplicate() {
+    let deps = mock_dependencies(&[]);
+    let call = |token_id: &str| Cw721Call {
+        require_success: false,
+        collection: Addr::unchecked("collection"),
+        token_id: token_id.to_string(),
+    };
+
+    let q: Cw721BatchResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Cw721Batch {
+                include_cause: None,
+                include_expired: None,
+                queries: vec![
+                    call("1"),
+                    call("1"),
+                    call(MOCK_BURNED_TOKEN),
+                    call("2"),
+                    call(MOCK_BURNED_TOKEN),
+                ],
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.return_data.len(), 5);
+    assert_eq!(q.return_data[0], q.return_data[1]);
+    assert_eq!(q.return_data[2], q.return_data[4]);
+    assert_eq!(q.executed, 5);
+    assert_eq!(deps.querier.executed(), 5);
+}
+
+#[test_case(br#"{"a":[1,-2.5e10,true,false,null],"b":{"c":"d"}}"#; "nested")]
+#[test_case(br#""esc\"aped\\\n\u0001""#; "escaped string")]
+#[test_case(br#"340282366920938463463374607431768211455"#; "large number")]
+#[test_case(br#"[]"#; "empty array")]
+#[test_case(br#"{}"#; "empty object")]
+fn json_roundtrip(data: &[u8]) {
+    assert_eq!(json::from_slice(data).unwrap().to_vec(), data);
+}
+
+#[test_case(br#"{"a":1,}"#; "trailing comma")]
+#[test_case(br#"{"a":01}"#; "leading zero")]
+#[test_case(br#"{"a":1} x"#; "trailing characters")]
+#[test_case(br#""\u12""#; "short unicode escape")]
+#[test_case(br#"[1, 2"#; "unterminated array")]
+fn json_invalid(data: &[u8]) {
+    assert!(matches!(
+        json::from_slice(data).unwrap_err(),
+        StdError::ParseErr { .. }
+    ));
+}
+
+#[test_case("", Some(br#"{"a/b":[{"~c":"d"}],"e":1}"#); "whole document")]
+#[test_case("/a~1b/0/~0c", Some(br#""d""#); "escaped tokens")]
+#[test_case("/e", Some(br#"1"#); "number")]
+#[test_case("/a~1b/1", None; "index out of bounds")]
+#[test_case("/a~1b/00", None; "leading zero index")]
+#[test_case("e", None; "missing slash")]
+fn json_pointer(pointer: &str, expected: Option<&[u8]>) {
+    let value = json::from_slice(br#"{"a/b":[{"~c":"d"}],"e":1}"#).unwrap();
+
+    assert_eq!(
+        value.pointer(pointer).map(|v| v.to_vec()),
+        expected.map(|e| e.to_vec())
+    );
+}
+
+#[test_case("/e", Some(br#"{"a/b":[{"~c":"d"}],"e":true}"#); "replace")]
+#[test_case("/f", Some(br#"{"a/b":[{"~c":"d"}],"e":1,"f":true}"#); "insert")]
+#[test_case("/a~1b/0", Some(br#"{"a/b":[true],"e":1}"#); "array item")]
+#[test_case("/a~1b/1", None; "index out of bounds")]
+#[test_case("/g/h", None; "missing parent")]
+fn json_set(pointer: &str, expected: Option<&[u8]>) {
+    let mut value = json::from_slice(br#"{"a/b":[{"~c":"d"}],"e":1}"#).unwrap();
+
+    assert_eq!(
+        value
+            .set(pointer, json::Value::Bool(true))
+            .map(|_| value.to_vec()),
+        expected.map(|e| e.to_vec())
+    );
+}
+
+fn chain_call(address: &str, data: &str) -> Call {
+    Call {
+        address: Addr::unchecked(address),
+        data: Binary::from(data.as_bytes()),
+        select: None,
+    }
+}
+
+#[test_case("factory"; "word")]
+#[test_case("with \"quotes\""; "escaped")]
+fn chain(x: &str) {
+    let deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Chain {
+                queries: vec![
+                    Call {
+                        address: Addr::unchecked(""),
+                        data: to_binary(&MockQueryMsg::StructStr(x.to_string())).unwrap(),
+                        select: None,
+                    },
+                    chain_call("", r#"{"StructStr":"$0/another_result"}"#),
+                    chain_call("$0/result", r#"{"balance":{"address":"$1/result"}}"#),
+                    chain_call("", r#"{"StructAmount":7}"#),
+                    chain_call("", r#"{"StructAmount":"$3/list/2"}"#),
+                ],
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.return_data.len(), 5);
+    assert!(q.return_data.iter().all(|r| r.success));
+
+    let second: AnotherStructResponse = from_binary(&q.return_data[1].data).unwrap();
+    assert_eq!(second.result, x.to_uppercase());
+
+    let balance: Cw20BalanceResponse = from_binary(&q.return_data[2].data).unwrap();
+    assert_eq!(balance.balance.u128(), (x.len() * 1001) as u128);
+
+    let last: SomeStructResponse = from_binary(&q.return_data[4].data).unwrap();
+    assert_eq!(last.amount, Uint128::from(27u64));
+}
+
+#[test_case("$$1INCH", "$1INCH"; "digit")]
+#[test_case("$$$0/result", "$$0/result"; "placeholder")]
+#[test_case("$$", "$"; "dollar")]
+fn chain_escape(data: &str, expected: &str) {
+    let deps = mock_dependencies(&[]);
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Chain {
+                queries: vec![chain_call(
+                    "$$0/result",
+                    &format!(r#"{{"StructStr":"{}"}}"#, data),
+                )],
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let result: AnotherStructResponse = from_binary(&q.return_data[0].data).unwrap();
+    assert_eq!(result.result, expected);
+}
+
+#[test_case(r#"{"StructStr":"$1/result"}"#, "", "$1/result"; "future index")]
+#[test_case(r#"{"StructStr":"$0/missing"}"#, "", "$0/missing"; "missing path")]
+#[test_case(r#"{"StructStr":"$2"}"#, "", "$2"; "missing index")]
+#[test_case(r#"{"StructStr":"ok"}"#, "$0/list", "$0/list"; "address not a string")]
+fn chain_unresolved(data: &str, address: &str, placeholder: &str) {
+    let deps = mock_dependencies(&[]);
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Chain {
+            queries: vec![
+                Call {
+                    address: Addr::unchecked(""),
+                    data: to_binary(&MockQueryMsg::Struct).unwrap(),
+                    select: None,
+                },
+                chain_call(address, data),
+            ],
+            response_format: None,
+            max_response_bytes: None,
+        },
+    )
+    .unwrap_err();
+
+    let err = aggregate_error(&err);
+    assert_eq!((err.index, err.kind), (1, ErrorKind::Multicall));
+    assert!(
+        err.message
+            .starts_with(&format!("Unresolved placeholder {}", placeholder)),
+        "{}",
+        err.message
+    );
+}
+
+#[test]
+fn response_format() {
+    let deps = mock_dependencies(&[]);
+    let env = env_with_height(5);
+
+    let queries = vec![
+        Call {
+            address: Addr::unchecked(""),
+            data: to_binary(&MockQueryMsg::StructAmount(1)).unwrap(),
+            select: None,
+        },
+        Call {
+            address: Addr::unchecked(""),
+            data: to_binary(&MockQueryMsg::One).unwrap(),
+            select: None,
+        },
+        Call {
+            address: Addr::unchecked(""),
+            data: to_binary(&MockQueryMsg::Str(String::from("hello"))).unwrap(),
+            select: None,
+        },
+        Call {
+            address: Addr::unchecked(""),
+            data: to_binary(&MockQueryMsg::FailContract).unwrap(),
+            select: None,
+        },
+    ];
+    let msg = |response_format| QueryMsg::BlockTryAggregate {
+        require_success: None,
+        include_cause: Some(true),
+        queries: queries.clone(),
+        response_format,
+        min_height: None,
+        max_height: None,
+        min_time: None,
+        include_error: None,
+        budget: None,
+        max_response_bytes: None,
+    };
+
+    let q = query(deps.as_ref(), env.clone(), msg(Some(ResponseFormat::Json))).unwrap();
+
+    assert_eq!(
+        q,
+        Binary::from(
+            br#"{"block":5,"time":"1571797419879305533","chain_id":"cosmos-testnet-14002","contract":"cosmos2contract","return_data":[{"success":true,"json":{"address":"random_amount","amount":"3","list":[1,2,3]}},{"success":true,"json":1},{"success":true,"data":"aGVsbG8="},{"success":false,"json":"Querier contract error: error"}],"executed":4}"#
+        )
+    );
+
+    assert_eq!(
+        query(
+            deps.as_ref(),
+            env.clone(),
+            msg(Some(ResponseFormat::Base64))
+        )
+        .unwrap(),
+        query(deps.as_ref(), env, msg(None)).unwrap()
+    );
+}
+
+#[test_case(vec!["/amount"], br#"{"/amount":"300"}"#; "single field")]
+#[test_case(vec!["/list/1", "/address"], br#"{"/list/1":101,"/address":"random_amount"}"#; "many fields")]
+#[test_case(vec![""], br#"{"":{"address":"random_amount","amount":"300","list":[100,101,102]}}"#; "whole response")]
+fn select(select: Vec<&str>, expected: &[u8]) {
+    let deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let select = Some(select.into_iter().map(String::from).collect::<Vec<_>>());
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Aggregate {
+                queries: vec![
+                    Call {
+                        address: Addr::unchecked(""),
+                        data: to_binary(&MockQueryMsg::StructAmount(100)).unwrap(),
+                        select: select.clone(),
+                    },
+                    Call {
+                        address: Addr::unchecked(""),
+                        data: to_binary(&MockQueryMsg::StructAmount(100)).unwrap(),
+                        select: None,
+                    },
+                ],
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.return_data[0].data, Binary::from(expected));
+    let full: SomeStructResponse = from_binary(&q.return_data[1].data).unwrap();
+    assert_eq!(full.amount, Uint128::from(300u64));
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::TryAggregateOptional {
+                include_cause: None,
+                queries: vec![CallOptional {
+                    require_success: true,
+                    address: Addr::unchecked(""),
+                    data: to_binary(&MockQueryMsg::StructAmount(100)).unwrap(),
+                    select,
+                }],
+                response_format: None,
+                include_error: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.return_data[0].data, Binary::from(expected));
+}
+
+#[test_case(MockQueryMsg::StructAmount(1), "/missing"; "missing path")]
+#[test_case(MockQueryMsg::Str(String::from("not json")), "/amount"; "not json")]
+fn select_unresolved(msg: MockQueryMsg, pointer: &str) {
+    let deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let queries = vec![Call {
+        address: Addr::unchecked(""),
+        data: to_binary(&msg).unwrap(),
+        select: Some(vec![pointer.to_string()]),
+    }];
+
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Aggregate {
+            queries: queries.clone(),
+            response_format: None,
+            max_response_bytes: None,
+        },
+    )
+    .unwrap_err();
+
+    let err = aggregate_error(&err);
+    assert_eq!(err.index, 0);
+    assert!(err.message.starts_with("Unresolved selection"));
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::TryAggregate {
+                require_success: None,
+                include_cause: Some(true),
+                queries,
+                response_format: None,
+                include_error: None,
+                budget: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert!(!q.return_data[0].success);
+    assert!(from_binary::<String>(&q.return_data[0].data)
+        .unwrap()
+        .starts_with("Unresolved selection"));
+}
+
+#[test_case(vec![0, 1, 2], 3; "all unique")]
+#[test_case(vec![0, 0, 0, 0], 1; "all identical")]
+#[test_case(vec![0, 1, 0, 2, 1], 3; "interleaved duplicates")]
+#[test_case(vec![], 0; "empty")]
+fn deduplicate(amounts: Vec<u64>, unique: u64) {
+    let deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let queries = amounts
+        .iter()
+        .map(|amount| Call {
+            address: Addr::unchecked(""),
+            data: to_binary(&MockQueryMsg::StructAmount(*amount)).unwrap(),
+            select: None,
+        })
+        .collect::<Vec<_>>();
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Aggregate {
+                queries: queries.clone(),
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.executed, unique);
+    assert_eq!(deps.querier.executed(), unique);
+    assert_eq!(q.return_data.len(), amounts.len());
+    for (amount, res) in amounts.iter().zip(q.return_data) {
+        let res: SomeStructResponse = from_binary(&res.data).unwrap();
+        assert_eq!(res.amount, Uint128::from(amount * 3));
+    }
+
+    let q: BlockAggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::BlockTryAggregateOptional {
+                include_cause: None,
+                queries: queries
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, call)| CallOptional {
+                        require_success: i % 2 == 0,
+                        address: call.address,
+                        data: call.data,
+                        select: Some(vec![format!("/list/{}", i % 3)]),
+                    })
+                    .collect(),
+                response_format: None,
+                min_height: None,
+                max_height: None,
+                min_time: None,
+                include_error: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.executed, unique);
+    assert_eq!(deps.querier.executed(), unique * 2);
+}
+
+#[test]
+fn deduplicate_mixed_select() {
+    let deps = mock_dependencies(&[]);
+    let call = |select: Option<&str>| Call {
+        address: Addr::unchecked(""),
+        data: to_binary(&MockQueryMsg::StructAmount(1)).unwrap(),
+        select: select.map(|pointer| vec![pointer.to_string()]),
+    };
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Aggregate {
+                queries: vec![call(None), call(Some("/amount")), call(None)],
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.executed, 1);
+    assert_eq!(q.return_data[0], q.return_data[2]);
+    assert_eq!(q.return_data[1].data, Binary::from(br#"{"/amount":"3"}"#));
+}
+
+#[test]
+fn deduplicate_typed_results() {
+    let deps = mock_dependencies(&[]);
+    let addresses = vec![
+        Addr::unchecked("a"),
+        Addr::unchecked("b"),
+        Addr::unchecked("a"),
+    ];
+
+    let q: ContractInfosResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ContractInfos {
+                require_success: None,
+                include_cause: None,
+                addresses: addresses.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(q.executed, 2);
+
+    let q: BalancesResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AllBalances {
+                addresses: addresses.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(q.executed, 2);
+
+    let q: Cw20BalancesResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Cw20Balances {
+                require_success: None,
+                include_cause: None,
+                tokens: addresses,
+                holders: vec![Addr::unchecked("alice")],
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(q.executed, 2);
+}
+
+fn execute_msgs() -> Vec<CosmosMsg> {
+    vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("pair"),
+            msg: to_binary(&MockQueryMsg::One).unwrap(),
+            funds: vec![coin(100, "uluna")],
+        }),
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("alice"),
+            amount: vec![coin(50, "uluna"), coin(7, "uusd")],
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("token"),
+            msg: to_binary(&MockQueryMsg::One).unwrap(),
+            funds: vec![],
+        }),
+    ]
+}
+
+#[test_case(vec![coin(150, "uluna"), coin(7, "uusd")], vec![]; "exact funds")]
+#[test_case(vec![coin(200, "uluna"), coin(10, "uusd"), coin(1, "ukrw")], vec![coin(1, "ukrw"), coin(50, "uluna"), coin(3, "uusd")]; "refund remaining")]
+fn execute_aggregate(funds: Vec<Coin>, refund: Vec<Coin>) {
+    let mut deps = mock_dependencies(&[coin(1_000_000, "uluna")]);
+    let msgs = execute_msgs();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &funds),
+        ExecuteMsg::Aggregate { msgs: msgs.clone() },
+    )
+    .unwrap();
+
+    let dispatched = res
+        .messages
+        .iter()
+        .map(|m| m.msg.clone())
+        .collect::<Vec<_>>();
+    match refund[..] {
+        [] => assert_eq!(dispatched, msgs),
+        _ => {
+            assert_eq!(dispatched[..msgs.len()], msgs[..]);
+            assert_eq!(
+                dispatched[msgs.len()],
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("sender"),
+                    amount: refund,
+                })
+            );
+        }
+    }
+    assert!(res.messages.iter().all(|m| m.reply_on == ReplyOn::Never));
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("method", "aggregate"),
+            attr("msg_0", "wasm/execute"),
+            attr("msg_1", "bank/send"),
+            attr("msg_2", "wasm/execute"),
+        ]
+    );
+}
+
+#[test_case(vec![], 0, coin(100, "uluna"); "no funds")]
+#[test_case(vec![coin(149, "uluna"), coin(7, "uusd")], 1, coin(50, "uluna"); "short of one denom")]
+#[test_case(vec![coin(150, "uluna")], 1, coin(7, "uusd"); "missing denom")]
+fn execute_aggregate_insufficient_funds(funds: Vec<Coin>, index: usize, missing: Coin) {
+    let mut deps = mock_dependencies(&[coin(1_000_000, "uluna")]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &funds),
+        ExecuteMsg::Aggregate {
+            msgs: execute_msgs(),
+        },
+    )
+    .unwrap_err();
+
+    assert!(
+        matches!(err, ContractError::InsufficientFunds { index: i, coin } if i == index && coin == missing)
+    );
+}
+
+#[test_case(StakingMsg::Undelegate { validator: String::from("validator"), amount: coin(1, "uluna") }.into(), "staking/undelegate"; "undelegate")]
+#[test_case(StakingMsg::Delegate { validator: String::from("validator"), amount: coin(1, "uluna") }.into(), "staking/delegate"; "delegate")]
+#[test_case(DistributionMsg::SetWithdrawAddress { address: String::from("thief") }.into(), "distribution/set_withdraw_address"; "set withdraw address")]
+#[test_case(WasmMsg::Migrate { contract_addr: String::from("contract"), new_code_id: 1, msg: Binary::default() }.into(), "wasm/migrate"; "migrate")]
+#[test_case(WasmMsg::UpdateAdmin { contract_addr: String::from("contract"), admin: String::from("thief") }.into(), "wasm/update_admin"; "update admin")]
+#[test_case(WasmMsg::ClearAdmin { contract_addr: String::from("contract") }.into(), "wasm/clear_admin"; "clear admin")]
+fn execute_unsupported_message(msg: CosmosMsg, kind: &str) {
+    let mut deps = mock_dependencies(&[]);
+    let msgs = [execute_msgs(), vec![msg]].concat();
+    let index = msgs.len() - 1;
+    let funds = vec![coin(150, "uluna"), coin(7, "uusd")];
+
+    for msg in [
+        ExecuteMsg::Aggregate { msgs: msgs.clone() },
+        ExecuteMsg::TryAggregate {
+            include_cause: None,
+            msgs: msgs.clone(),
+        },
+    ] {
+        let err = execute(deps.as_mut(), mock_env(), mock_info("sender", &funds), msg).unwrap_err();
+
+        assert!(
+            matches!(&err, ContractError::UnsupportedMessage { index: i, kind: k } if *i == index && k == kind),
+            "{}",
+            err
+        );
+    }
+}
+
+fn reply_msg(id: u64, data: Option<Binary>) -> Reply {
+    Reply {
+        id,
+        result: match data {
+            Some(data) => SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(data),
+            }),
+            None => SubMsgResult::Err(String::from("execute wasm contract failed")),
+        },
+    }
+}
+
+#[test_case(false, false, vec![coin(150, "uluna"), coin(7, "uusd")], vec![]; "all succeed")]
+#[test_case(false, false, vec![coin(160, "uluna"), coin(7, "uusd")], vec![coin(10, "uluna")]; "remaining refunded")]
+#[test_case(true, false, vec![coin(150, "uluna"), coin(7, "uusd")], vec![coin(50, "uluna"), coin(7, "uusd")]; "failure refunded")]
+#[test_case(true, true, vec![coin(160, "uluna"), coin(7, "uusd")], vec![coin(60, "uluna"), coin(7, "uusd")]; "failure refunded with cause")]
+fn execute_try_aggregate(fail: bool, include_cause: bool, funds: Vec<Coin>, refund: Vec<Coin>) {
+    let mut deps = mock_dependencies(&[coin(1_000_000, "uluna")]);
+    let msgs = execute_msgs();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &funds),
+        ExecuteMsg::TryAggregate {
+            include_cause: Some(include_cause),
+            msgs: msgs.clone(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages,
+        msgs.into_iter()
+            .enumerate()
+            .map(|(i, msg)| SubMsg::reply_always(msg, i as u64))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("method", "try_aggregate"),
+            attr("msg_0", "wasm/execute"),
+            attr("msg_1", "bank/send"),
+            attr("msg_2", "wasm/execute"),
+        ]
+    );
+
+    let replies = vec![
+        reply_msg(0, Some(Binary::from(b"first"))),
+        reply_msg(1, if fail { None } else { Some(Binary::default()) }),
+        reply_msg(2, Some(Binary::from(b"third"))),
+    ];
+    let res = replies
+        .into_iter()
+        .map(|msg| reply(deps.as_mut(), mock_env(), msg).unwrap())
+        .last()
+        .unwrap();
+
+    let expected: Vec<CallResult> = vec![
+        CallResult::from_data(Binary::from(b"first")),
+        match (fail, include_cause) {
+            (false, _) => CallResult::from_data(Binary::default()),
+            (true, false) => CallResult::from_failure(Binary::default()),
+            (true, true) => CallResult::from_failure(
+                to_binary(&String::from("execute wasm contract failed")).unwrap(),
+            ),
+        },
+        CallResult::from_data(Binary::from(b"third")),
+    ];
+    assert_eq!(
+        from_binary::<Vec<CallResult>>(&res.data.unwrap()).unwrap(),
+        expected
+    );
+    match refund[..] {
+        [] => assert!(res.messages.is_empty()),
+        _ => assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: String::from("sender"),
+                amount: refund,
+            })]
+        ),
+    }
+}
+
+#[test]
+fn execute_try_aggregate_empty() {
+    let mut deps = mock_dependencies(&[]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &[coin(5, "uluna")]),
+        ExecuteMsg::TryAggregate {
+            include_cause: None,
+            msgs: vec![],
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        from_binary::<Vec<CallResult>>(&res.data.unwrap()).unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: String::from("sender"),
+            amount: vec![coin(5, "uluna")],
+        })]
+    );
+}
+
+#[test]
+fn execute_try_aggregate_in_progress() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = ExecuteMsg::TryAggregate {
+        include_cause: None,
+        msgs: vec![CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("alice"),
+            amount: vec![],
+        })],
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info("sender", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::BatchInProgress {}));
+
+    let err = reply(deps.as_mut(), mock_env(), reply_msg(3, None)).unwrap_err();
+    assert!(matches!(err, ContractError::UnexpectedReply { id: 3 }));
+}
+
+#[test_case(false; "optional failure")]
+#[test_case(true; "required failure")]
+fn execute_try_aggregate_optional(require_success: bool) {
+    let mut deps = mock_dependencies(&[]);
+    let msgs = execute_msgs()
+        .into_iter()
+        .enumerate()
+        .map(|(i, msg)| MsgOptional {
+            require_success: i != 1 || require_success,
+            msg,
+        })
+        .collect::<Vec<_>>();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &[coin(150, "uluna"), coin(7, "uusd")]),
+        ExecuteMsg::TryAggregateOptional {
+            include_cause: None,
+            msgs,
+        },
+    )
+    .unwrap();
+    assert!(res.messages.iter().all(|m| m.reply_on == ReplyOn::Always));
+    assert_eq!(res.attributes[0], attr("method", "try_aggregate_optional"));
+
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        reply_msg(0, Some(Binary::default())),
+    )
+    .unwrap();
+    let res = reply(deps.as_mut(), mock_env(), reply_msg(1, None));
+    match require_success {
+        true => assert!(matches!(
+            res.unwrap_err(),
+            ContractError::RequiredMessageFailed { index: 1, cause } if cause == "execute wasm contract failed"
+        )),
+        false => {
+            assert!(res.unwrap().data.is_none());
+            let res = reply(
+                deps.as_mut(),
+                mock_env(),
+                reply_msg(2, Some(Binary::default())),
+            )
+            .unwrap();
+            assert_eq!(
+                from_binary::<Vec<CallResult>>(&res.data.unwrap()).unwrap(),
+                vec![
+                    CallResult::from_data(Binary::default()),
+                    CallResult::from_failure(Binary::default()),
+                    CallResult::from_data(Binary::default()),
+                ]
+            );
+        }
+    }
+}
+
+fn smart_call(msg: MockQueryMsg) -> Call {
+    Call {
+        address: Addr::unchecked(""),
+        data: to_binary(&msg).unwrap(),
+        select: None,
+    }
+}
+
+fn assertion(source: AssertSource, path: &str, op: CompareOp, value: &str) -> Assertion {
+    Assertion {
+        source,
+        path: path.to_string(),
+        op,
+        value: value.to_string(),
+    }
+}
+
+#[test_case("/amount", CompareOp::Gte, "100000000", Some(r#""100000000""#); "decimal gte")]
+#[test_case("/amount", CompareOp::Lt, "100000000.5", Some(r#""100000000""#); "decimal lt fraction")]
+#[test_case("/list/5", CompareOp::Eq, "5.0", Some("5"); "number eq")]
+#[test_case("/address", CompareOp::Eq, "random", Some(r#""random""#); "string eq")]
+#[test_case("/list", CompareOp::Ne, "[]", Some("[0,1,2,3,4,5]"); "array ne")]
+#[test_case("/amount", CompareOp::Gt, "100000000", None; "decimal gt fails")]
+#[test_case("/address", CompareOp::Lt, "zzz", None; "string not comparable")]
+#[test_case("/missing", CompareOp::Eq, "1", None; "missing path")]
+fn guarded_aggregate(path: &str, op: CompareOp, value: &str, compared: Option<&str>) {
+    let deps = mock_dependencies(&[]);
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GuardedAggregate {
+            queries: vec![
+                CallKind::Query(smart_call(MockQueryMsg::Struct)),
+                CallKind::Assert(assertion(AssertSource::Index(0), path, op, value)),
+                CallKind::Assert(assertion(
+                    AssertSource::Query(smart_call(MockQueryMsg::StructAmount(2))),
+                    "/amount",
+                    CompareOp::Eq,
+                    "6",
+                )),
+            ],
+            response_format: None,
+            max_response_bytes: None,
+        },
+    );
+
+    match compared {
+        Some(compared) => {
+            let q: AggregateResult = from_binary(&res.unwrap()).unwrap();
+            assert_eq!(q.return_data.len(), 3);
+            assert_eq!(q.return_data[1].data, Binary::from(compared.as_bytes()));
+            assert_eq!(q.return_data[2].data, Binary::from(br#""6""#));
+            assert_eq!(q.executed, 2);
+        }
+        None => {
+            let err = aggregate_error(&res.unwrap_err());
+            assert_eq!((err.index, err.address), (1, None));
+            assert!(
+                err.message
+                    .starts_with(&format!("Assertion failed on {}", path)),
+                "{}",
+                err.message
+            );
+        }
+    }
+}
+
+#[test_case(CompareOp::Eq, "6", true; "holds")]
+#[test_case(CompareOp::Lt, "6", false; "fails")]
+fn execute_assert(op: CompareOp, value: &str, holds: bool) {
+    let mut deps = mock_dependencies(&[]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &[]),
+        ExecuteMsg::Assert {
+            assertions: vec![assertion(
+                AssertSource::Query(smart_call(MockQueryMsg::StructAmount(2))),
+                "/amount",
+                op,
+                value,
+            )],
+        },
+    );
+
+    match holds {
+        true => assert_eq!(
+            res.unwrap().attributes,
+            vec![attr("method", "assert"), attr("assertions", "1")]
+        ),
+        false => match res.unwrap_err() {
+            ContractError::Std(err) => {
+                let err = aggregate_error(&err);
+                assert_eq!((err.index, err.address), (0, Some(String::new())));
+                assert!(err.message.starts_with("Assertion failed on /amount"));
+            }
+            err => panic!("unexpected error {}", err),
+        },
+    }
+}
+
+#[test]
+fn execute_assert_index_source() {
+    let mut deps = mock_dependencies(&[]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &[]),
+        ExecuteMsg::Assert {
+            assertions: vec![assertion(AssertSource::Index(0), "", CompareOp::Eq, "1")],
+        },
+    )
+    .unwrap_err();
+
+    match err {
+        ContractError::Std(err) => assert!(aggregate_error(&err)
+            .message
+            .ends_with("no prior result at index 0")),
+        err => panic!("unexpected error {}", err),
+    }
+}
+
+#[test_case(Some(10), None, None, None; "at min height")]
+#[test_case(None, Some(10), Some(mock_env().block.time), None; "at max height and min time")]
+#[test_case(Some(11), None, None, Some("height 10 is below min_height 11"); "below min height")]
+#[test_case(None, Some(9), None, Some("height 10 is above max_height 9"); "above max height")]
+#[test_case(None, None, Some(mock_env().block.time.plus_seconds(1)), Some("time 1571797419.879305533 is before min_time 1571797420.879305533"); "before min time")]
+fn block_window(
+    min_height: Option<u64>,
+    max_height: Option<u64>,
+    min_time: Option<Timestamp>,
+    err: Option<&str>,
+) {
+    let deps = mock_dependencies(&[]);
+
+    let res = query(
+        deps.as_ref(),
+        env_with_height(10),
+        QueryMsg::BlockAggregateRaw {
+            queries: vec![raw_call("token", b"key")],
+            response_format: None,
+            min_height,
+            max_height,
+            min_time,
+            max_response_bytes: None,
+        },
+    );
+
+    match err {
+        None => assert_eq!(
+            from_binary::<BlockAggregateResult>(&res.unwrap())
+                .unwrap()
+                .block,
+            10
+        ),
+        Some(err) => match res.unwrap_err() {
+            StdError::GenericErr { msg } => assert_eq!(
+                from_slice::<BlockWindowError>(msg.as_bytes()).unwrap(),
+                BlockWindowError {
+                    height: 10,
+                    time: mock_env().block.time,
+                    min_height,
+                    max_height,
+                    min_time,
+                    message: err.to_string(),
+                }
+            ),
+            err => panic!("unexpected error {}", err),
+        },
+    }
+}
+
+#[test_case(false; "without cause")]
+#[test_case(true; "with cause")]
+fn try_aggregate_include_error(include_cause: bool) {
+    let deps = mock_dependencies(&[]);
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TryAggregate {
+                require_success: None,
+                include_cause: Some(include_cause),
+                include_error: Some(true),
+                queries: vec![
+                    Call {
+                        address: Addr::unchecked("pair"),
+                        data: to_binary(&MockQueryMsg::FailSystem).unwrap(),
+                        select: None,
+                    },
+                    Call {
+                        address: Addr::unchecked("token"),
+                        data: to_binary(&MockQueryMsg::FailContract).unwrap(),
+                        select: None,
+                    },
+                    Call {
+                        address: Addr::unchecked("token"),
+                        data: to_binary(&MockQueryMsg::Struct).unwrap(),
+                        select: Some(vec![String::from("/missing")]),
+                    },
+                    smart_call(MockQueryMsg::One),
+                ],
+                response_format: None,
+                budget: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let error = |kind, system_error: Option<&str>, address: &str, message: &str| {
+        Some(CallError {
+            kind,
+            system_error: system_error.map(String::from),
+            address: Some(address.to_string()),
+            message: message.to_string(),
+        })
+    };
+    assert_eq!(
+        q.return_data
+            .iter()
+            .map(|r| r.error.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            error(
+                ErrorKind::System,
+                Some("unknown"),
+                "pair",
+                "Unknown system error"
+            ),
+            error(ErrorKind::Contract, None, "token", "error"),
+            error(
+                ErrorKind::Multicall,
+                None,
+                "token",
+                "Unresolved selection /missing: path not found in response"
+            ),
+            None,
+        ]
+    );
+    assert_eq!(
+        from_binary::<String>(&q.return_data[1].data).ok(),
+        include_cause.then(|| String::from("Querier contract error: error"))
+    );
+}
+
+#[test]
+fn try_aggregate_raw_include_error() {
+    let deps = mock_dependencies(&[]);
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TryAggregateRaw {
+                require_success: None,
+                include_cause: None,
+                include_error: Some(true),
+                queries: vec![raw_call(MOCK_MISSING_CONTRACT, b"key")],
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        q.return_data[0].error,
+        Some(CallError {
+            kind: ErrorKind::System,
+            system_error: Some(String::from("no_such_contract")),
+            address: Some(MOCK_MISSING_CONTRACT.to_string()),
+            message: format!("No such contract: {}", MOCK_MISSING_CONTRACT),
+        })
+    );
+}
+
+#[test_case(None, None, 5, None; "unbounded")]
+#[test_case(Some(2), None, 2, Some(2); "max calls")]
+#[test_case(Some(0), None, 1, Some(1); "always progresses")]
+#[test_case(None, Some(3), 3, Some(3); "max payload")]
+#[test_case(Some(10), Some(100), 5, None; "within budget")]
+fn try_aggregate_budget(
+    max_calls: Option<u64>,
+    max_payload_bytes: Option<u64>,
+    returned: usize,
+    next_index: Option<u64>,
+) {
+    let deps = mock_dependencies(&[]);
+
+    let q: BlockAggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env_with_height(4),
+            QueryMsg::BlockTryAggregate {
+                require_success: None,
+                include_cause: None,
+                include_error: None,
+                queries: (0..5)
+                    .map(|i| smart_call(MockQueryMsg::Str(i.to_string())))
+                    .collect(),
+                budget: Some(Budget {
+                    max_calls,
+                    max_payload_bytes,
+                }),
+                response_format: None,
+                min_height: None,
+                max_height: None,
+                min_time: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.return_data.len(), returned);
+    assert_eq!(q.executed, returned as u64);
+    assert_eq!(q.next_index, next_index);
+    for (i, r) in q.return_data.iter().enumerate() {
+        assert_eq!(r.data, Binary::from(i.to_string().as_bytes()));
+    }
+}
+
+#[test_case(None, vec![None, None, None, None]; "unbounded")]
+#[test_case(Some(8), vec![None, None, Some(true), None]; "skips oversized")]
+#[test_case(Some(3), vec![None, Some(true), Some(true), None]; "fits later")]
+#[test_case(Some(0), vec![Some(true), Some(true), Some(true), Some(true)]; "nothing fits")]
+fn max_response_bytes(max_response_bytes: Option<u64>, truncated: Vec<Option<bool>>) {
+    let deps = mock_dependencies(&[]);
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TryAggregate {
+                require_success: None,
+                include_cause: None,
+                include_error: None,
+                queries: ["ab", "abcde", "abcdefghij", "a"]
+                    .iter()
+                    .map(|s| smart_call(MockQueryMsg::Str(s.to_string())))
+                    .collect(),
+                budget: None,
+                response_format: None,
+                max_response_bytes,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        q.return_data
+            .iter()
+            .map(|r| r.truncated)
+            .collect::<Vec<_>>(),
+        truncated
+    );
+    assert!(q
+        .return_data
+        .iter()
+        .all(|r| r.success && (r.truncated.is_some() == r.data.is_empty())));
+}
+
+#[test]
+fn max_response_bytes_other_aggregates() {
+    let deps = mock_dependencies(&[]);
+    let raw = |key: &[u8]| RawCall {
+        address: Addr::unchecked("contract"),
+        key: Binary::from(key),
+    };
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AggregateRaw {
+                queries: vec![raw(b"a"), raw(b"abcdefghij"), raw(b"b")],
+                response_format: None,
+                max_response_bytes: Some(14),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        q.return_data
+            .iter()
+            .map(|r| r.truncated)
+            .collect::<Vec<_>>(),
+        vec![None, Some(true), None]
+    );
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Chain {
+                queries: vec![
+                    smart_call(MockQueryMsg::Str(String::from("abcde"))),
+                    smart_call(MockQueryMsg::Str(String::from("ab"))),
+                ],
+                response_format: None,
+                max_response_bytes: Some(4),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        q.return_data
+            .iter()
+            .map(|r| r.truncated)
+            .collect::<Vec<_>>(),
+        vec![Some(true), None]
+    );
+}
+
+#[test_case(10, 4, MOCK_ACCOUNTS, None; "until exhausted")]
+#[test_case(2, 2, 6, Some("5"); "until max pages")]
+fn paginate(max_pages: u64, pages: u64, items: u64, next_start_after: Option<&str>) {
+    let deps = mock_dependencies(&[]);
+
+    let q: PaginateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Paginate {
+                address: Addr::unchecked("vault"),
+                data: to_binary(&MockQueryMsg::Accounts {
+                    start_after: None,
+                    limit: 3,
+                })
+                .unwrap(),
+                start_after: String::from("/Accounts/start_after"),
+                items: String::from("/accounts"),
+                cursor: Some(String::from("/id")),
+                max_pages,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.pages, pages);
+    assert_eq!(
+        from_slice::<Vec<MockAccount>>(&q.items).unwrap(),
+        (0..items).map(|id| MockAccount { id }).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        q.next_start_after,
+        next_start_after.map(|next| Binary::from(next.as_bytes()))
+    );
+}
+
+#[test_case("/accounts/0", Some("/id"), "Unresolved selection /accounts/0: items are not an array"; "items not an array")]
+#[test_case("/accounts", Some("/missing"), "Unresolved selection /missing: path not found in item"; "missing cursor")]
+fn paginate_unresolved(items: &str, cursor: Option<&str>, message: &str) {
+    let deps = mock_dependencies(&[]);
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Paginate {
+            address: Addr::unchecked("vault"),
+            data: to_binary(&MockQueryMsg::Accounts {
+                start_after: None,
+                limit: 3,
+            })
+            .unwrap(),
+            start_after: String::from("/Accounts/start_after"),
+            items: items.to_string(),
+            cursor: cursor.map(String::from),
+            max_pages: 3,
+        },
+    )
+    .unwrap_err();
+
+    let err = aggregate_error(&err);
+    assert_eq!((err.index, err.kind), (0, ErrorKind::Multicall));
+    assert_eq!(err.message, message);
+}
+
+#[test]
+fn paginate_no_pages() {
+    let deps = mock_dependencies(&[]);
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Paginate {
+            address: Addr::unchecked("vault"),
+            data: to_binary(&MockQueryMsg::Accounts {
+                start_after: None,
+                limit: 3,
+            })
+            .unwrap(),
+            start_after: String::from("/Accounts/start_after"),
+            items: String::from("/accounts"),
+            cursor: Some(String::from("/id")),
+            max_pages: 0,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err("max_pages must be at least 1"));
+    assert_eq!(deps.querier.executed(), 0);
+}
+
+#[test_case(None, None; "suppressed")]
+#[test_case(Some(true), Some(1); "required")]
+fn map_addresses(require_success: Option<bool>, error_at: Option<u64>) {
+    let deps = mock_dependencies(&[]);
+    let addresses = vec![
+        Addr::unchecked("vault_a"),
+        Addr::unchecked(MOCK_MISSING_CONTRACT),
+        Addr::unchecked("vault_abc"),
+        Addr::unchecked("vault_a"),
+    ];
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::MapAddresses {
+            require_success,
+            include_cause: None,
+            data: to_binary(&Cw20QueryMsg::Balance {
+                address: String::from("holder"),
+            })
+            .unwrap(),
+            addresses: addresses.clone(),
+            response_format: None,
+            max_response_bytes: None,
+        },
+    );
+
+    if let Some(index) = error_at {
+        assert_eq!(aggregate_error(&res.unwrap_err()).index, index);
+        return;
+    }
+    let q: AggregateResult = from_binary(&res.unwrap()).unwrap();
+    assert_eq!(q.return_data.len(), addresses.len());
+    assert_eq!(q.executed, 3);
+    for (address, r) in addresses.iter().zip(q.return_data) {
+        match address.as_str() {
+            MOCK_MISSING_CONTRACT => assert!(!r.success),
+            _ => assert_eq!(
+                from_binary::<Cw20BalanceResponse>(&r.data).unwrap().balance,
+                Uint128::from((address.as_str().len() * 1000 + 6) as u64)
+            ),
+        }
+    }
+}
+
+#[test_case(r#"{"balance":{"address":"$v"}}"#, "$v"; "whole string")]
+#[test_case(r#"{"balance":{"address":"holder_$v"}}"#, "holder_$v"; "inside string")]
+fn map_values(template: &str, holder: &str) {
+    let deps = mock_dependencies(&[]);
+    let values = vec![
+        String::from("a"),
+        String::from("with \"quotes\""),
+        String::from("a"),
+    ];
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::MapValues {
+                require_success: None,
+                include_cause: None,
+                address: Addr::unchecked("token"),
+                template: Binary::from(template.as_bytes()),
+                values: values.clone(),
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.executed, 2);
+    for (value, r) in values.iter().zip(q.return_data) {
+        assert_eq!(
+            from_binary::<Cw20BalanceResponse>(&r.data).unwrap().balance,
+            Uint128::from((5000 + holder.replace("$v", value).len()) as u64)
+        );
+    }
+}
+
+#[test_case("$vault", "$vault"; "longer word")]
+#[test_case("$$v", "$v"; "escaped")]
+#[test_case("$$$v", "$x"; "escaped before value")]
+#[test_case("$v-$v_1", "x-x_1"; "many tokens")]
+#[test_case("$ $1", "$ $1"; "lone dollar")]
+fn map_values_template(template: &str, expected: &str) {
+    let deps = mock_dependencies(&[]);
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::MapValues {
+                require_success: Some(true),
+                include_cause: None,
+                address: Addr::unchecked("contract"),
+                template: to_binary(&MockQueryMsg::StructStr(template.to_string())).unwrap(),
+                values: vec![String::from("x")],
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let res: AnotherStructResponse = from_binary(&q.return_data[0].data).unwrap();
+    assert_eq!(res.result, expected);
+}
+
+#[test]
+fn map_values_include_cause() {
+    let deps = mock_dependencies(&[]);
+
+    let q: AggregateResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::MapValues {
+                require_success: None,
+                include_cause: Some(true),
+                address: Addr::unchecked(MOCK_MISSING_CONTRACT),
+                template: Binary::from(br#"{"balance":{"address":"$v"}}"#),
+                values: vec![String::from("holder")],
+                response_format: None,
+                max_response_bytes: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert!(!q.return_data[0].success);
+    assert_eq!(
+        from_binary::<String>(&q.return_data[0].data).unwrap(),
+        format!(
+            "Querier system error: No such contract: {}",
+            MOCK_MISSING_CONTRACT
+        )
+    );
+}
+
+#[test_case(vec![1, 5, 2], "/amount", "24", "3", "15", "8", "6"; "odd count")]
+#[test_case(vec![4, 1, 2, 1], "/amount", "24", "3", "12", "6", "4.5"; "even count")]
+#[test_case(vec![1, 2], "/list/1", "5", "2", "3", "2.5", "2.5"; "number field")]
+fn reduce(
+    amounts: Vec<u64>,
+    path: &str,
+    sum: &str,
+    min: &str,
+    max: &str,
+    mean: &str,
+    median: &str,
+) {
+    let deps = mock_dependencies(&[]);
+
+    let q: ReduceResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Reduce {
+                queries: amounts
+                    .iter()
+                    .map(|amount| smart_call(MockQueryMsg::StructAmount(*amount)))
+                    .collect(),
+                path: path.to_string(),
+                include_results: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.count, amounts.len() as u64);
+    assert_eq!(q.sum, Decimal256::from_str(sum).unwrap());
+    assert_eq!(q.min, Decimal256::from_str(min).unwrap());
+    assert_eq!(q.max, Decimal256::from_str(max).unwrap());
+    assert_eq!(q.mean, Decimal256::from_str(mean).unwrap());
+    assert_eq!(q.median, Decimal256::from_str(median).unwrap());
+    assert_eq!(q.return_data, None);
+}
+
+#[test]
+fn reduce_overflow() {
+    let deps = mock_dependencies(&[]);
+    let huge = String::from("1") + &"0".repeat(59);
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Reduce {
+            queries: vec![
+                smart_call(MockQueryMsg::StructStr(huge.clone())),
+                smart_call(MockQueryMsg::StructStr(huge)),
+            ],
+            path: String::from("/result"),
+            include_results: None,
+        },
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, StdError::Overflow { .. }), "{}", err);
+}
+
+#[test]
+fn reduce_include_results() {
+    let deps = mock_dependencies(&[]);
+
+    let q: ReduceResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Reduce {
+                queries: vec![smart_call(MockQueryMsg::StructAmount(1))],
+                path: String::from("/amount"),
+                include_results: Some(true),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let return_data = q.return_data.unwrap();
+    assert_eq!(return_data.len(), 1);
+    assert!(return_data[0].success);
+}
+
+#[test_case(vec![], None; "empty batch")]
+#[test_case(vec![MockQueryMsg::StructAmount(1), MockQueryMsg::StructStr(String::from("a"))], Some(1); "not a decimal")]
+fn reduce_unresolved(msgs: Vec<MockQueryMsg>, index: Option<u64>) {
+    let deps = mock_dependencies(&[]);
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Reduce {
+            queries: msgs.into_iter().map(smart_call).collect(),
+            path: String::from("/amount"),
+            include_results: None,
+        },
+    )
+    .unwrap_err();
+
+    match index {
+        Some(index) => {
+            let err = aggregate_error(&err);
+            assert_eq!(err.index, index);
+            assert_eq!(err.kind, ErrorKind::Multicall);
+        }
+        None => assert_eq!(err, StdError::generic_err("Nothing to reduce")),
+    }
+}
+
+/// Oracle answering `price` updated `age` seconds ago, or failing when `price` is empty.
+fn oracle(address: &str, price: &str, age: u64) -> OracleSource {
+    oracle_at(address, price, mock_env().block.time.seconds() - age)
+}
+
+fn oracle_at(address: &str, price: &str, last_updated: u64) -> OracleSource {
+    let msg = match price {
+        "" => MockQueryMsg::FailContract,
+        price => MockQueryMsg::Price {
+            price: price.to_string(),
+            last_updated,
+        },
+    };
+
+    OracleSource {
+        address: Addr::unchecked(address),
+        data: to_binary(&msg).unwrap(),
+        price_path: String::from("/price"),
+        time_path: String::from("/last_updated"),
+    }
+}
+
+#[test_case(vec![("a", "1.5", 0), ("b", "1.2", 30), ("c", "1.9", 60)], "1.5", vec!["a", "b", "c"]; "all fresh")]
+#[test_case(vec![("a", "1.5", 0), ("b", "1.2", 30), ("c", "9.9", 61)], "1.35", vec!["a", "b"]; "stale discarded")]
+#[test_case(vec![("a", "1.5", 0), ("b", "", 0), ("c", "1.9", 0)], "1.7", vec!["a", "c"]; "failed discarded")]
+#[test_case(vec![("a", "1.5", 0), ("b", "not a price", 0)], "1.5", vec!["a"]; "invalid discarded")]
+#[test_case(vec![("a", "100000000000000000000000000000000000000000000000000000000000", 0), ("b", "110000000000000000000000000000000000000000000000000000000000", 0)], "105000000000000000000000000000000000000000000000000000000000", vec!["a", "b"]; "median near max")]
+fn oracle_price(oracles: Vec<(&str, &str, u64)>, median: &str, sources: Vec<&str>) {
+    let deps = mock_dependencies(&[]);
+
+    let q: OraclePriceResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OraclePrice {
+                oracles: oracles
+                    .iter()
+                    .map(|(address, price, age)| oracle(address, price, *age))
+                    .collect(),
+                max_age: 60,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.median, Decimal256::from_str(median).unwrap());
+    assert_eq!(
+        q.sources
+            .iter()
+            .map(|source| source.address.as_str())
+            .collect::<Vec<&str>>(),
+        sources
+    );
+    for source in q.sources {
+        let (_, price, age) = oracles
+            .iter()
+            .find(|(address, _, _)| *address == source.address.as_str())
+            .unwrap();
+        assert_eq!(source.price, Decimal256::from_str(price).unwrap());
+        assert_eq!(
+            source.time,
+            Timestamp::from_seconds(mock_env().block.time.seconds() - age)
+        );
+    }
+}
+
+#[test]
+fn oracle_price_all_stale() {
+    let deps = mock_dependencies(&[]);
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::OraclePrice {
+            oracles: vec![oracle("a", "1.5", 120), oracle("b", "1.2", 61)],
+            max_age: 60,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, StdError::generic_err("No fresh oracle price"));
+}
+
+#[test_case(mock_env().block.time.seconds() + 1; "one second ahead")]
+#[test_case(mock_env().block.time.plus_seconds(115 * 24 * 3600).seconds(); "days ahead")]
+#[test_case(mock_env().block.time.minus_seconds(3600).nanos(); "stale nanoseconds")]
+#[test_case(u64::MAX; "max")]
+fn oracle_price_invalid_time(last_updated: u64) {
+    let deps = mock_dependencies(&[]);
+
+    let q: OraclePriceResult = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OraclePrice {
+                oracles: vec![oracle("a", "1.5", 10), oracle_at("b", "9.9", last_updated)],
+                max_age: 60,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(q.median, Decimal256::from_str("1.5").unwrap());
+    assert_eq!(q.sources.len(), 1);
+    assert_eq!(q.sources[0].address, Addr::unchecked("a"));
+}